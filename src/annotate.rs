@@ -0,0 +1,70 @@
+// src/annotate.rs
+// Tesseract word boxes from TSV output: drawn onto a copy of the OCR input image,
+// or exported as JSON.
+
+use anyhow::{Context, Result};
+use image::Rgba;
+use imageproc::drawing::draw_hollow_rect_mut;
+use imageproc::rect::Rect;
+use serde::Serialize;
+use std::path::Path;
+
+/// Tesseract's TSV level for individual words.
+const WORD_LEVEL: &str = "5";
+const BOX_COLOR: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+/// One recognized word, its pixel bounding box and Tesseract's confidence (0-100).
+#[derive(Debug, Clone, Serialize)]
+pub struct WordBox {
+    pub text: String,
+    pub left: i32,
+    pub top: i32,
+    pub width: u32,
+    pub height: u32,
+    pub conf: f32,
+}
+
+/// Parses the word rows of Tesseract's TSV output, skipping empty words.
+pub fn parse_tsv_words(tsv: &str) -> Vec<WordBox> {
+    tsv.lines()
+        .skip(1) // header row
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split('\t').collect();
+            if cols.len() < 12 || cols[0] != WORD_LEVEL {
+                return None;
+            }
+            let text = cols[11].trim();
+            if text.is_empty() {
+                return None;
+            }
+            Some(WordBox {
+                text: text.to_string(),
+                left: cols[6].parse().ok()?,
+                top: cols[7].parse().ok()?,
+                width: cols[8].parse().ok()?,
+                height: cols[9].parse().ok()?,
+                conf: cols[10].parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Saves a copy of `image_path` with each word box outlined to `output_path`.
+pub fn save_annotated(image_path: &Path, words: &[WordBox], output_path: &Path) -> Result<()> {
+    let mut img = image::open(image_path)
+        .with_context(|| format!("Failed to open OCR image {:?} for annotation", image_path))?
+        .to_rgba8();
+    for word in words.iter().filter(|w| w.width > 0 && w.height > 0) {
+        let rect = Rect::at(word.left, word.top).of_size(word.width, word.height);
+        draw_hollow_rect_mut(&mut img, rect, BOX_COLOR);
+    }
+    img.save(output_path)
+        .with_context(|| format!("Failed to save annotated image to {:?}", output_path))
+}
+
+/// Writes the word boxes as a JSON array to `output_path`.
+pub fn save_json(words: &[WordBox], output_path: &Path) -> Result<()> {
+    let json = serde_json::to_string_pretty(words).context("Failed to serialize word boxes")?;
+    std::fs::write(output_path, json)
+        .with_context(|| format!("Failed to write word boxes to {:?}", output_path))
+}
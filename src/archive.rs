@@ -0,0 +1,87 @@
+// src/archive.rs
+// Lossless archive of triggered clipboard bitmaps (`--archive-bitmaps`) for OCR eval sets.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// One `<stem>.json` manifest, written next to the archived files.
+#[derive(Serialize)]
+struct Manifest<'a> {
+    timestamp_unix_ms: u128,
+    source: &'a str,
+    raw_file: String,
+    raw_bytes: usize,
+    decoded_file: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    decode_error: Option<String>,
+    ocr_text: Option<&'a str>,
+    ocr_error: Option<String>,
+}
+
+/// Writes `raw` exactly as received, its decoded PNG (if it decodes) and a manifest.
+/// Returns the shared file stem.
+pub fn archive_bitmap(
+    dir: &Path,
+    raw: &[u8],
+    source: &str,
+    ocr_result: &Result<String>,
+) -> Result<String> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create archive dir {:?}", dir))?;
+    let timestamp_unix_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let stem = unique_stem(dir, timestamp_unix_ms);
+
+    // Clipboard bitmaps arrive as BMP file bytes; keep them byte for byte.
+    let raw_file = format!("{}.raw.bmp", stem);
+    fs::write(dir.join(&raw_file), raw)
+        .with_context(|| format!("Failed to write {:?}", dir.join(&raw_file)))?;
+
+    let mut manifest = Manifest {
+        timestamp_unix_ms,
+        source,
+        raw_file,
+        raw_bytes: raw.len(),
+        decoded_file: None,
+        width: None,
+        height: None,
+        decode_error: None,
+        ocr_text: ocr_result.as_ref().ok().map(String::as_str),
+        ocr_error: ocr_result.as_ref().err().map(|e| format!("{:#}", e)),
+    };
+    match image::load_from_memory(raw) {
+        Ok(img) => {
+            let decoded_file = format!("{}.png", stem);
+            img.save_with_format(dir.join(&decoded_file), image::ImageFormat::Png)
+                .with_context(|| format!("Failed to write {:?}", dir.join(&decoded_file)))?;
+            manifest.decoded_file = Some(decoded_file);
+            manifest.width = Some(img.width());
+            manifest.height = Some(img.height());
+        }
+        Err(e) => manifest.decode_error = Some(e.to_string()),
+    }
+
+    let manifest_path = dir.join(format!("{}.json", stem));
+    fs::write(&manifest_path, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {:?}", manifest_path))?;
+    Ok(stem)
+}
+
+/// `bitmap_<ms>`, with a counter appended if a trigger in the same millisecond took it.
+fn unique_stem(dir: &Path, timestamp_unix_ms: u128) -> String {
+    let base = format!("bitmap_{}", timestamp_unix_ms);
+    let mut stem = base.clone();
+    let mut counter = 1;
+    while dir.join(format!("{}.json", stem)).exists() {
+        stem = format!("{}_{}", base, counter);
+        counter += 1;
+    }
+    stem
+}
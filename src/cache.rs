@@ -0,0 +1,69 @@
+// src/cache.rs
+// On-disk cache of recognized text, keyed by a hash of the input and settings.
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// Hashes each part (length-prefixed, so part boundaries matter) into a hex key.
+pub fn cache_key(parts: &[&[u8]]) -> String {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update((part.len() as u64).to_le_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+pub struct ResultCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+impl ResultCache {
+    /// Opens (creating if needed) a cache in `dir`. Entries older than `ttl` are ignored.
+    pub fn open(dir: &Path, ttl: Option<Duration>) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create cache directory {:?}", dir))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            ttl,
+        })
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.txt", key))
+    }
+
+    /// Returns the cached text for `key`, or `None` if missing or expired.
+    pub fn get(&self, key: &str) -> Option<String> {
+        let path = self.entry_path(key);
+        let metadata = fs::metadata(&path).ok()?;
+        if let Some(ttl) = self.ttl {
+            let age = metadata
+                .modified()
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .unwrap_or(Duration::MAX);
+            if age > ttl {
+                println!("Cache entry {} expired; ignoring it.", key);
+                let _ = fs::remove_file(&path);
+                return None;
+            }
+        }
+        fs::read_to_string(&path).ok()
+    }
+
+    pub fn put(&self, key: &str, text: &str) -> Result<()> {
+        let path = self.entry_path(key);
+        fs::write(&path, text).with_context(|| format!("Failed to write cache entry {:?}", path))
+    }
+}
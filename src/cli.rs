@@ -0,0 +1,371 @@
+// src/cli.rs
+// Command-line options for the recognition pipeline, flattened into the `ocrp`
+// binary's arguments and usable by any other clap-based front end.
+
+use crate::{
+    diarize::DiarizeBackend,
+    image_ops,
+    input::{DirPolicy, InputSettings, MissingKeyPolicy, UnknownFilePolicy},
+    ocr::{OcrSettings, TempImageFormat},
+    pipeline::{CacheSettings, Config},
+    text_transform,
+    transcribe::trans::{SilenceSplit, TranscribeOptions, TranscriptFormat},
+};
+use std::{path::PathBuf, time::Duration};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct PipelineArgs {
+    #[arg(
+        short = 'l',
+        long,
+        default_value = "eng",
+        help = "Tesseract language code(s)."
+    )]
+    pub lang: String,
+    #[arg(long, default_value = "tesseract", help = "Tesseract command/path.")]
+    pub tesseract_cmd: String,
+    #[arg(long, help = "Path to Tesseract data directory.")]
+    pub tessdata_path: Option<String>,
+    #[arg(long, help = "Tesseract page segmentation mode (--psm).")]
+    pub psm: Option<u32>,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "PSMs to retry in order when OCR returns empty text (e.g. 6,4,11)."
+    )]
+    pub psm_fallback: Vec<u32>,
+    #[arg(
+        long,
+        value_parser = parse_encoding,
+        help = "Encoding to decode Tesseract output with when it isn't valid UTF-8 (e.g. latin1, windows-1252, shift_jis)."
+    )]
+    pub ocr_encoding: Option<&'static encoding_rs::Encoding>,
+    #[arg(
+        long,
+        help = "Replace invalid UTF-8 in Tesseract output instead of failing (when --ocr-encoding isn't set)."
+    )]
+    pub lossy_utf8: bool,
+    #[arg(
+        long,
+        help = "Program run on the prepared image before Tesseract, as '<program> <image> <output>'. It writes the cleaned image to <output> or overwrites <image>; on failure the original image is used."
+    )]
+    pub pre_command: Option<String>,
+    #[arg(
+        long,
+        value_parser = image_ops::parse_color_mask,
+        help = "Only OCR the region drawn in this color: '<RRGGBB>,<tolerance>' crops to the bounding box of matching pixels."
+    )]
+    pub color_mask: Option<image_ops::ColorMask>,
+    #[arg(
+        long,
+        help = "Invert image colors before OCR, for light text on a dark background."
+    )]
+    pub invert: bool,
+    #[arg(
+        long,
+        conflicts_with = "invert",
+        help = "Invert image colors before OCR when the image is mostly dark, e.g. dark-mode screenshots."
+    )]
+    pub auto_invert: bool,
+    #[arg(
+        long,
+        help = "Pass PNG images to Tesseract as is, skipping decode/re-encode and the blank check. Ignored with --auto-rotate, --color-mask, --invert or --auto-invert, and for PNGs with an alpha channel unless --flatten-alpha never."
+    )]
+    pub fast_image: bool,
+    #[arg(
+        long,
+        help = "Detect each image's (or TIFF page's) orientation with Tesseract and rotate it upright before OCR."
+    )]
+    pub auto_rotate: bool,
+    #[arg(
+        long,
+        help = "Prepend a line with Tesseract's detected script and orientation (with confidences) to the OCR text. Reflects the image after --auto-rotate."
+    )]
+    pub include_osd: bool,
+    #[arg(
+        long,
+        help = "When Tesseract exits with an error but still printed text, log its stderr as a warning and use the text instead of failing."
+    )]
+    pub accept_partial_ocr: bool,
+    #[arg(
+        long,
+        default_value_t = 300,
+        help = "Resolution Tesseract assumes for clipboard images, which carry no DPI metadata (0 lets Tesseract estimate)."
+    )]
+    pub ocr_dpi: u32,
+    #[arg(
+        long,
+        help = "Best-effort Markdown output from Tesseract hOCR: taller lines become headings, blocks become paragraphs."
+    )]
+    pub markdown: bool,
+    #[arg(long, help = "Additional Tesseract CLI args.", num_args = 0..)]
+    pub tesseract_args: Vec<String>,
+    #[arg(
+        long,
+        default_value = "yt-dlp",
+        help = "yt-dlp command/path for URL audio."
+    )]
+    pub ytdlp_cmd: String,
+    #[arg(long, help = "Only download the first N seconds of URL audio.")]
+    pub ytdlp_max_duration_secs: Option<u64>,
+    #[arg(
+        long,
+        default_value = "\\n\\n",
+        value_parser = text_transform::parse_escaped,
+        help = "Text that replaces each form-feed page break in multi-page OCR output (supports \\n, \\t)."
+    )]
+    pub page_separator: String,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TempImageFormat::Png,
+        help = "Format of the temporary image passed to Tesseract."
+    )]
+    pub temp_image_format: TempImageFormat,
+    #[arg(
+        long,
+        help = "Log Tesseract's stderr warnings (e.g. resolution estimates) even when it succeeds."
+    )]
+    pub show_tesseract_warnings: bool,
+    #[arg(
+        long,
+        help = "Directory for caching recognized text by image or audio hash."
+    )]
+    pub cache_dir: Option<PathBuf>,
+    #[arg(long, help = "Seconds before a cache entry expires (default: never).")]
+    pub cache_ttl: Option<u64>,
+    #[arg(long, help = "Bypass the result cache even if --cache-dir is set.")]
+    pub no_cache: bool,
+    #[arg(
+        long,
+        default_value = "whisper-1",
+        help = "OpenAI transcription model."
+    )]
+    pub transcribe_model: String,
+    #[arg(
+        long,
+        value_enum,
+        help = "Transcription output format. Defaults per source: srt for video and video URLs, text for audio and the microphone (see [transcribe_formats] in the config)."
+    )]
+    pub transcribe_format: Option<TranscriptFormat>,
+    #[arg(long, help = "Only transcribe the first N seconds of audio/video.")]
+    pub transcribe_duration_secs: Option<u64>,
+    #[arg(
+        long,
+        help = "Log how long transcription requests take, and whether the OpenAI client was reused."
+    )]
+    pub timings: bool,
+    #[arg(
+        long,
+        help = "Drop transcript segments whose speech confidence (0-1) is below this, refusing to paste if none remain. Needs a verbose_json model such as whisper-1."
+    )]
+    pub min_speech_confidence: Option<f32>,
+    #[arg(
+        long,
+        conflicts_with_all = ["min_speech_confidence", "split_on_silence", "transcribe_format"],
+        help = "Paste a 'start<TAB>word' table of word timings instead of the flat transcript. Needs a model with word timestamps such as whisper-1; others fall back to text."
+    )]
+    pub word_timestamps: bool,
+    #[arg(
+        long,
+        value_parser = parse_temperature,
+        help = "Transcription sampling temperature (0.0-1.0). Lower values are more deterministic and hallucinate less on hard audio; unset uses the API default."
+    )]
+    pub transcribe_temperature: Option<f32>,
+    #[arg(
+        long,
+        help = "Split audio at pauses (ffmpeg silencedetect), transcribe each piece and join them with blank lines."
+    )]
+    pub split_on_silence: bool,
+    #[arg(
+        long,
+        default_value_t = -30.0,
+        allow_hyphen_values = true,
+        help = "Loudness in dB below which audio counts as silence for --split-on-silence."
+    )]
+    pub silence_threshold_db: f64,
+    #[arg(
+        long,
+        default_value_t = 0.7,
+        help = "Minimum pause length in seconds that splits audio for --split-on-silence."
+    )]
+    pub silence_min_secs: f64,
+    #[arg(
+        long,
+        default_value_t = 0.5,
+        help = "Seconds each --split-on-silence segment overlaps the previous one; words repeated at the seam are removed (0 for hard cuts)."
+    )]
+    pub chunk_overlap_secs: f64,
+    #[arg(
+        long,
+        help = "Transcribe the left and right channels of a stereo file separately, labeled [L] and [R]."
+    )]
+    pub per_channel: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = MissingKeyPolicy::Error,
+        help = "How audio/video triggers behave when no OpenAI API key is set."
+    )]
+    pub on_missing_key: MissingKeyPolicy,
+    #[arg(long, help = "OpenAI API Key (overrides .env/env var).")]
+    pub openai_api_key: Option<String>,
+    #[arg(
+        long,
+        conflicts_with_all = ["per_channel", "split_on_silence", "min_speech_confidence", "word_timestamps", "transcribe_format"],
+        help = "Transcribe with speaker labels by POSTing the audio to this diarization service instead of OpenAI. Output is 'Speaker N: text' lines; see the README for the request and response format."
+    )]
+    pub diarize_backend: Option<String>,
+    #[arg(
+        long,
+        help = "Bearer token sent to --diarize-backend (overrides the DIARIZE_API_KEY env var)."
+    )]
+    pub diarize_api_key: Option<String>,
+    #[arg(
+        long,
+        default_value_t = 2.0,
+        help = "Luminance/alpha variance below which a clipboard image is treated as blank (0 disables)."
+    )]
+    pub blank_variance_threshold: f64,
+    #[arg(
+        long,
+        default_value_t = 16,
+        help = "Images narrower or shorter than this many pixels are rejected without running Tesseract (0 disables)."
+    )]
+    pub min_image_dimension: u32,
+    #[arg(
+        long,
+        default_value_t = 100_000_000,
+        help = "Reject images whose header declares more pixels than this, before decoding them (0 for no limit)."
+    )]
+    pub max_pixels: u64,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = image_ops::FlattenAlpha::Auto,
+        help = "Transparency handling before OCR: auto drops an all-zero alpha channel (bogus in many clipboard bitmaps), always also composites other transparency onto white."
+    )]
+    pub flatten_alpha: image_ops::FlattenAlpha,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = UnknownFilePolicy::Error,
+        help = "How to handle a single copied file that isn't a known audio, video or image type."
+    )]
+    pub unknown_file_policy: UnknownFilePolicy,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DirPolicy::Error,
+        help = "How to handle a copied folder: error, process its first supported file, or all of them."
+    )]
+    pub dir_policy: DirPolicy,
+    #[arg(
+        long,
+        default_value_t = 20,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Most files processed from one folder with --dir-policy all-files."
+    )]
+    pub max_files: u64,
+}
+
+impl PipelineArgs {
+    pub fn ocr_settings(&self) -> OcrSettings {
+        OcrSettings {
+            tesseract_cmd: self.tesseract_cmd.clone(),
+            tessdata_path: self.tessdata_path.clone(),
+            lang: self.lang.clone(),
+            psm: self.psm,
+            psm_fallback: self.psm_fallback.clone(),
+            auto_rotate: self.auto_rotate,
+            color_mask: self.color_mask,
+            invert: self.invert,
+            auto_invert: self.auto_invert,
+            fast_image: self.fast_image,
+            dpi: self.ocr_dpi,
+            extra_args: self.tesseract_args.clone(),
+            pre_command: self.pre_command.clone(),
+            min_image_dimension: self.min_image_dimension,
+            max_pixels: self.max_pixels,
+            flatten_alpha: self.flatten_alpha,
+            blank_variance_threshold: self.blank_variance_threshold,
+            temp_image_format: self.temp_image_format,
+            markdown: self.markdown,
+            include_osd: self.include_osd,
+            encoding: self.ocr_encoding,
+            lossy_utf8: self.lossy_utf8,
+            accept_partial: self.accept_partial_ocr,
+            show_warnings: self.show_tesseract_warnings,
+            page_separator: self.page_separator.clone(),
+        }
+    }
+
+    pub fn transcribe_options(&self) -> TranscribeOptions {
+        TranscribeOptions {
+            model: self.transcribe_model.clone(),
+            max_duration_secs: self.transcribe_duration_secs,
+            min_speech_confidence: self.min_speech_confidence,
+            split_on_silence: self.split_on_silence.then_some(SilenceSplit {
+                threshold_db: self.silence_threshold_db,
+                min_duration_secs: self.silence_min_secs,
+                overlap_secs: self.chunk_overlap_secs,
+            }),
+            per_channel: self.per_channel,
+            temperature: self.transcribe_temperature,
+            format: self.transcribe_format.unwrap_or_default(),
+            word_timestamps: self.word_timestamps,
+        }
+    }
+
+    /// `--cache-dir` unless `--no-cache` is set.
+    pub fn cache_settings(&self) -> Option<CacheSettings> {
+        match &self.cache_dir {
+            Some(dir) if !self.no_cache => Some(CacheSettings {
+                dir: dir.clone(),
+                ttl: self.cache_ttl.map(Duration::from_secs),
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn config(&self) -> Config {
+        Config {
+            ocr: self.ocr_settings(),
+            openai_api_key: self.openai_api_key.clone(),
+            transcribe: self.transcribe_options(),
+            diarize: self.diarize_backend.as_ref().map(|url| DiarizeBackend {
+                url: url.clone(),
+                api_key: self.diarize_api_key.clone(),
+            }),
+            cache: self.cache_settings(),
+            timings: self.timings,
+        }
+    }
+
+    pub fn input_settings(&self) -> InputSettings {
+        InputSettings {
+            unknown_file_policy: self.unknown_file_policy,
+            dir_policy: self.dir_policy,
+            max_files: self.max_files as usize,
+            on_missing_key: self.on_missing_key,
+            max_duration_secs: self.transcribe_duration_secs,
+            ytdlp_cmd: self.ytdlp_cmd.clone(),
+            ytdlp_max_duration_secs: self.ytdlp_max_duration_secs,
+            page_separator: self.page_separator.clone(),
+        }
+    }
+}
+
+fn parse_temperature(value: &str) -> Result<f32, String> {
+    let temperature: f32 = value.parse().map_err(|e| format!("{}", e))?;
+    if (0.0..=1.0).contains(&temperature) {
+        Ok(temperature)
+    } else {
+        Err(format!("must be between 0.0 and 1.0, got {}", temperature))
+    }
+}
+
+fn parse_encoding(label: &str) -> Result<&'static encoding_rs::Encoding, String> {
+    encoding_rs::Encoding::for_label(label.as_bytes())
+        .ok_or_else(|| format!("unknown encoding '{}'", label))
+}
@@ -0,0 +1,93 @@
+// src/config.rs
+// Optional TOML config file for settings that don't fit on the command line.
+
+use crate::easy_rdev_key::PTTKey;
+use crate::paste::PasteMethod;
+use crate::transcribe::trans::TranscriptFormat;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::Path, path::PathBuf};
+
+/// Chooses a paste method for foreground windows whose executable name or title
+/// contains `pattern` (case-insensitive).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PasteTargetRule {
+    pub pattern: String,
+    pub method: PasteMethod,
+}
+
+/// Chooses the Tesseract language(s) for foreground windows whose executable name
+/// or title contains `pattern` (case-insensitive).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LangTargetRule {
+    pub pattern: String,
+    pub lang: String,
+}
+
+/// A Tesseract build and/or model directory used instead of the global
+/// `--tesseract-cmd`/`--tessdata-path` when OCR runs with `lang`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TesseractOverride {
+    pub lang: String,
+    pub tesseract_cmd: Option<String>,
+    pub tessdata_path: Option<String>,
+}
+
+/// Default `--transcribe-format` per kind of source, used when the flag isn't given.
+/// Unset entries fall back to text for audio and the microphone, srt for video.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TranscribeFormatDefaults {
+    pub audio: Option<TranscriptFormat>,
+    pub video: Option<TranscriptFormat>,
+    pub microphone: Option<TranscriptFormat>,
+}
+
+/// Values set here override the matching command-line options. Everything except
+/// `trigger_key` is re-applied when the file changes while running.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub trigger_key: Option<PTTKey>,
+    pub lang: Option<String>,
+    pub psm: Option<u32>,
+    pub beeps: Option<bool>,
+    pub paste_delay_ms: Option<u64>,
+    pub transcribe_model: Option<String>,
+    pub paste_targets: Vec<PasteTargetRule>,
+    pub lang_targets: Vec<LangTargetRule>,
+    pub tesseract_overrides: Vec<TesseractOverride>,
+    pub transcribe_formats: TranscribeFormatDefaults,
+}
+
+/// `%APPDATA%\ocrp\config.toml` on Windows.
+pub fn default_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("ocrp").join("config.toml"))
+}
+
+pub fn load(path: &Path) -> Result<Config> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file {:?}", path))?;
+    toml::from_str(&contents).with_context(|| format!("Failed to parse config file {:?}", path))
+}
+
+/// Case-insensitive substring match against the window's executable name or title.
+pub fn pattern_matches(pattern: &str, exe_name: Option<&str>, title: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    exe_name.is_some_and(|exe| exe.to_lowercase().contains(&pattern))
+        || title.to_lowercase().contains(&pattern)
+}
+
+/// The override for `lang` (e.g. `jpn` or `eng+jpn`): an exact match first, otherwise
+/// the first override naming one of its `+`-separated languages.
+pub fn find_tesseract_override<'a>(
+    overrides: &'a [TesseractOverride],
+    lang: &str,
+) -> Option<&'a TesseractOverride> {
+    overrides.iter().find(|rule| rule.lang == lang).or_else(|| {
+        overrides
+            .iter()
+            .find(|rule| lang.split('+').any(|part| part == rule.lang))
+    })
+}
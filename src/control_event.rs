@@ -0,0 +1,82 @@
+// src/control_event.rs
+// A Windows named event that scripts can signal to pause or resume the listener.
+
+use anyhow::{anyhow, Result};
+use std::{
+    ffi::OsStr,
+    os::windows::ffi::OsStrExt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+};
+use winapi::um::{
+    handleapi::CloseHandle,
+    synchapi::{CreateEventW, OpenEventW, SetEvent, WaitForSingleObject},
+    winbase::{INFINITE, WAIT_OBJECT_0},
+    winnt::EVENT_MODIFY_STATE,
+};
+
+pub const DEFAULT_EVENT_NAME: &str = "Local\\ocrp-toggle";
+
+fn wide(name: &str) -> Vec<u16> {
+    OsStr::new(name).encode_wide().chain(Some(0)).collect()
+}
+
+/// Creates the named event and spawns a thread that flips `enabled` each time it is signaled.
+pub fn spawn_toggle_listener(name: &str, enabled: Arc<AtomicBool>) -> Result<()> {
+    // Auto-reset, initially unsignaled.
+    let event = unsafe { CreateEventW(std::ptr::null_mut(), 0, 0, wide(name).as_ptr()) };
+    if event.is_null() {
+        return Err(anyhow!(
+            "Failed to create toggle event '{}': {}",
+            name,
+            std::io::Error::last_os_error()
+        ));
+    }
+    // Raw handles aren't Send; the event lives for the rest of the process.
+    let event_addr = event as usize;
+    let name = name.to_string();
+    thread::spawn(move || loop {
+        let event = event_addr as winapi::um::winnt::HANDLE;
+        if unsafe { WaitForSingleObject(event, INFINITE) } != WAIT_OBJECT_0 {
+            eprintln!(
+                "Warning: Waiting on toggle event '{}' failed. Stopping.",
+                name
+            );
+            return;
+        }
+        let was_enabled = enabled.fetch_xor(true, Ordering::SeqCst);
+        if was_enabled {
+            println!("Paused via toggle event '{}'. Triggers are ignored.", name);
+        } else {
+            println!("Resumed via toggle event '{}'.", name);
+        }
+    });
+    Ok(())
+}
+
+/// Signals the named event of a running instance, toggling its paused state.
+pub fn signal_toggle(name: &str) -> Result<()> {
+    unsafe {
+        let event = OpenEventW(EVENT_MODIFY_STATE, 0, wide(name).as_ptr());
+        if event.is_null() {
+            return Err(anyhow!(
+                "No running OCRP instance is listening on toggle event '{}': {}",
+                name,
+                std::io::Error::last_os_error()
+            ));
+        }
+        let ok = SetEvent(event);
+        CloseHandle(event);
+        if ok == 0 {
+            return Err(anyhow!(
+                "Failed to signal toggle event '{}': {}",
+                name,
+                std::io::Error::last_os_error()
+            ));
+        }
+    }
+    Ok(())
+}
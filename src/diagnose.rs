@@ -0,0 +1,112 @@
+// src/diagnose.rs
+// `--diagnose-image`: runs one image file through OCR and prints everything useful
+// for triaging poor recognition. Never touches the clipboard or pastes.
+
+use crate::{annotate, ocr, Args};
+use anyhow::{Context, Result};
+use std::{path::Path, time::Instant};
+
+pub fn run(path: &Path, args: &Args) -> Result<()> {
+    let data =
+        std::fs::read(path).with_context(|| format!("Failed to read image file {:?}", path))?;
+    println!("== Input ==");
+    println!("File: {:?} ({} bytes)", path, data.len());
+    match image::guess_format(&data) {
+        Ok(format) => println!("Detected format: {:?}", format),
+        Err(_) => println!("Detected format: unknown"),
+    }
+    match image::load_from_memory(&data) {
+        Ok(img) => println!(
+            "Dimensions: {}x{} ({:?})",
+            img.width(),
+            img.height(),
+            img.color()
+        ),
+        Err(e) => println!("Decode error: {}", e),
+    }
+    match embedded_dpi(&data) {
+        Some((x, y)) => println!("Embedded DPI: {:.0}x{:.0}", x, y),
+        None => println!("Embedded DPI: none recorded"),
+    }
+    if args.pipeline.ocr_dpi > 0 {
+        println!(
+            "DPI passed to Tesseract: {} (--ocr-dpi)",
+            args.pipeline.ocr_dpi
+        );
+    } else {
+        println!("DPI passed to Tesseract: none (Tesseract estimates it)");
+    }
+
+    println!("\n== Preparation ==");
+    let settings = args.pipeline_config().ocr;
+    let prepared = ocr::prepare_image(&data, &settings)?;
+
+    println!("\n== Tesseract ==");
+    let mut command = ocr::tesseract_command(prepared.path(), &settings, settings.psm);
+    println!("Command: {:?}", command);
+    let started = Instant::now();
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to run {:?}", command.get_program()))?;
+    println!("Time: {}ms", started.elapsed().as_millis());
+    println!("Exit status: {}", output.status);
+    println!(
+        "--- stdout ---\n{}",
+        String::from_utf8_lossy(&output.stdout).trim_end()
+    );
+    println!(
+        "--- stderr ---\n{}",
+        String::from_utf8_lossy(&output.stderr).trim_end()
+    );
+
+    println!("\n== Confidence ==");
+    let mut tsv_command = ocr::tesseract_command(prepared.path(), &settings, settings.psm);
+    tsv_command.arg("tsv");
+    let tsv_output = tsv_command
+        .output()
+        .context("Failed to run Tesseract for word confidences")?;
+    let words = annotate::parse_tsv_words(&String::from_utf8_lossy(&tsv_output.stdout));
+    if words.is_empty() {
+        println!("No words recognized.");
+    } else {
+        let mean = words.iter().map(|word| word.conf).sum::<f32>() / words.len() as f32;
+        let low = words.iter().filter(|word| word.conf < 60.0).count();
+        println!(
+            "{} words, mean confidence {:.1}, {} below 60.",
+            words.len(),
+            mean,
+            low
+        );
+    }
+    Ok(())
+}
+
+/// Resolution stored in a PNG `pHYs` chunk or a JPEG JFIF header, in dots per inch.
+fn embedded_dpi(data: &[u8]) -> Option<(f64, f64)> {
+    let be_u32 = |at: usize| Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?));
+    let be_u16 = |at: usize| Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?));
+
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let mut at = 8;
+        while let Some(length) = be_u32(at) {
+            let chunk_type = data.get(at + 4..at + 8)?;
+            if chunk_type == b"pHYs" && data.get(at + 16) == Some(&1) {
+                // Pixels per metre.
+                let to_dpi = |ppm: u32| ppm as f64 * 0.0254;
+                return Some((to_dpi(be_u32(at + 8)?), to_dpi(be_u32(at + 12)?)));
+            }
+            if chunk_type == b"IDAT" {
+                return None; // pHYs must come before the image data.
+            }
+            at += 12 + length as usize;
+        }
+    } else if data.starts_with(&[0xFF, 0xD8]) && data.get(6..11) == Some(b"JFIF\0") {
+        let (x, y) = (be_u16(14)? as f64, be_u16(16)? as f64);
+        return match data[13] {
+            1 => Some((x, y)),
+            2 => Some((x * 2.54, y * 2.54)),
+            _ => None,
+        };
+    }
+    None
+}
@@ -0,0 +1,173 @@
+// src/diarize.rs
+// `--diarize-backend`: transcription with speaker labels from an HTTP service, used
+// in place of the OpenAI request when configured.
+
+use crate::{
+    pipeline::Transcriber,
+    transcribe::trans::{TranscribeOptions, TranscriptFormat},
+};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+/// A diarization service that accepts an mp3 upload and returns speaker-labelled segments.
+#[derive(Debug, Clone)]
+pub struct DiarizeBackend {
+    pub url: String,
+    /// Sent as a bearer token when set.
+    pub api_key: Option<String>,
+}
+
+/// The response the backend must return: segments in spoken order.
+#[derive(Deserialize)]
+struct Response {
+    segments: Vec<Segment>,
+}
+
+#[derive(Deserialize)]
+struct Segment {
+    /// Any string or number identifying the speaker, e.g. `SPEAKER_00` or `0`.
+    speaker: serde_json::Value,
+    text: String,
+}
+
+impl Transcriber for DiarizeBackend {
+    fn name(&self) -> &'static str {
+        "diarization backend"
+    }
+
+    /// Posts `audio_path` as the multipart field `file` (plus `model`) and formats the
+    /// returned segments as `Speaker N: text` lines.
+    async fn transcribe(&self, audio_path: &Path, options: &TranscribeOptions) -> Result<String> {
+        if let Some(option) = unsupported_option(options) {
+            bail!("{} is not supported with --diarize-backend.", option);
+        }
+        let temp_dir = crate::tempfiles::dir("diarize")?;
+        let mp3_path = crate::transcribe::trans::ensure_mp3(
+            audio_path,
+            temp_dir.path(),
+            options.max_duration_secs,
+        )
+        .context("Failed to prepare MP3 file for diarization")?;
+        let audio = std::fs::read(&mp3_path)
+            .with_context(|| format!("Failed to read audio file {:?}", mp3_path))?;
+        let form = reqwest::multipart::Form::new()
+            .text("model", options.model.clone())
+            .part(
+                "file",
+                reqwest::multipart::Part::bytes(audio)
+                    .file_name("audio.mp3")
+                    .mime_str("audio/mpeg")?,
+            );
+
+        println!("Sending diarization request to {}...", self.url);
+        let mut request = reqwest::Client::new().post(&self.url).multipart(form);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await.context("Diarization request failed")?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!(
+                "Diarization backend returned {}:\n{}",
+                status,
+                body.trim()
+            ));
+        }
+        let response: Response = response.json().await.context(
+            "Diarization response was not the expected {\"segments\": [{\"speaker\", \"text\"}]} JSON",
+        )?;
+        println!("Received {} diarized segments.", response.segments.len());
+        Ok(format_segments(&response.segments))
+    }
+}
+
+/// The first requested option that only the OpenAI path implements.
+fn unsupported_option(options: &TranscribeOptions) -> Option<&'static str> {
+    if options.per_channel {
+        Some("--per-channel")
+    } else if options.split_on_silence.is_some() {
+        Some("--split-on-silence")
+    } else if options.min_speech_confidence.is_some() {
+        Some("--min-speech-confidence")
+    } else if options.word_timestamps {
+        Some("--word-timestamps")
+    } else if options.format != TranscriptFormat::Text {
+        Some("--transcribe-format")
+    } else {
+        None
+    }
+}
+
+/// Numbers speakers from 1 in order of first appearance and merges consecutive
+/// segments from the same speaker into one line.
+fn format_segments(segments: &[Segment]) -> String {
+    let mut speakers: Vec<&serde_json::Value> = Vec::new();
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for segment in segments {
+        let text = segment.text.trim();
+        if text.is_empty() {
+            continue;
+        }
+        let speaker = match speakers.iter().position(|known| **known == segment.speaker) {
+            Some(index) => index + 1,
+            None => {
+                speakers.push(&segment.speaker);
+                speakers.len()
+            }
+        };
+        match lines.last_mut() {
+            Some((last_speaker, line)) if *last_speaker == speaker => {
+                line.push(' ');
+                line.push_str(text);
+            }
+            _ => lines.push((speaker, text.to_string())),
+        }
+    }
+    lines
+        .iter()
+        .map(|(speaker, text)| format!("Speaker {}: {}", speaker, text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(json: &str) -> Vec<Segment> {
+        serde_json::from_str::<Response>(json).unwrap().segments
+    }
+
+    #[test]
+    fn numbers_speakers_by_first_appearance_and_merges_runs() {
+        let segments = segments(
+            r#"{"segments": [
+                {"speaker": "SPEAKER_01", "text": " Hi there."},
+                {"speaker": "SPEAKER_01", "text": "How are you?"},
+                {"speaker": "SPEAKER_00", "text": "Fine."},
+                {"speaker": "SPEAKER_01", "text": "Good."}
+            ]}"#,
+        );
+        assert_eq!(
+            format_segments(&segments),
+            "Speaker 1: Hi there. How are you?\nSpeaker 2: Fine.\nSpeaker 1: Good."
+        );
+    }
+
+    #[test]
+    fn accepts_numeric_speakers_and_skips_empty_text() {
+        let segments = segments(
+            r#"{"segments": [
+                {"speaker": 3, "text": "One."},
+                {"speaker": 0, "text": "  "},
+                {"speaker": 0, "text": "Two."}
+            ]}"#,
+        );
+        assert_eq!(
+            format_segments(&segments),
+            "Speaker 1: One.\nSpeaker 2: Two."
+        );
+    }
+}
@@ -1,7 +1,10 @@
 /// This is a copy of rdev::Key, so that #[derive(clap::ValueEnum)] works.
 ///
 /// I also added F13 through F24 for convenience.
-#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+#[derive(
+    clap::ValueEnum, serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq,
+)]
+#[serde(rename_all = "kebab-case")]
 pub enum PTTKey {
     /// Alt key on Linux and Windows (option key on macOS)
     Alt,
@@ -124,6 +127,7 @@ pub enum PTTKey {
     KpDelete,
     Function,
     #[clap(skip)]
+    #[serde(skip)]
     Unknown(u32),
 }
 
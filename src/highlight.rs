@@ -0,0 +1,81 @@
+// src/highlight.rs
+// ANSI syntax highlighting for text printed to the terminal (`--highlight`).
+
+use syntect::{
+    easy::HighlightLines,
+    highlighting::ThemeSet,
+    parsing::SyntaxSet,
+    util::{as_24_bit_terminal_escaped, LinesWithEndings},
+};
+
+const THEME: &str = "base16-ocean.dark";
+
+/// Guesses the language of OCR'd code from telltale tokens, as a file extension
+/// syntect knows. `None` means it looks like prose.
+pub fn guess_extension(text: &str) -> Option<&'static str> {
+    let trimmed = text.trim_start();
+    let has = |token: &str| text.contains(token);
+
+    if (trimmed.starts_with('{') || trimmed.starts_with('['))
+        && serde_json::from_str::<serde_json::Value>(text).is_ok()
+    {
+        Some("json")
+    } else if trimmed.starts_with("#!/bin/") || trimmed.starts_with("$ ") {
+        Some("sh")
+    } else if has("fn ") && (has("let ") || has("->") || has("::")) {
+        Some("rs")
+    } else if has("#include") {
+        Some("cpp")
+    } else if (has("def ") && has("):")) || (trimmed.starts_with("import ") && !has(";")) {
+        Some("py")
+    } else if has("public class") || has("public static") {
+        Some("java")
+    } else if has("function ") || has("=>") || (has("const ") && has(";")) {
+        Some("js")
+    } else if trimmed.starts_with('<') && has("</") {
+        Some("html")
+    } else if trimmed.to_uppercase().starts_with("SELECT ") {
+        Some("sql")
+    } else {
+        None
+    }
+}
+
+/// Wraps `text` in 24-bit ANSI colors for the language `extension`. Text that fails
+/// to highlight is returned as is.
+pub fn highlight(text: &str, extension: &str) -> String {
+    let syntax_set = SyntaxSet::load_defaults_newlines();
+    let theme_set = ThemeSet::load_defaults();
+    let Some(syntax) = syntax_set.find_syntax_by_extension(extension) else {
+        return text.to_string();
+    };
+    let mut highlighter = HighlightLines::new(syntax, &theme_set.themes[THEME]);
+
+    let mut output = String::with_capacity(text.len() * 2);
+    for line in LinesWithEndings::from(text) {
+        match highlighter.highlight_line(line, &syntax_set) {
+            Ok(ranges) => output.push_str(&as_24_bit_terminal_escaped(&ranges, false)),
+            Err(_) => return text.to_string(),
+        }
+    }
+    output.push_str("\x1b[0m");
+    output
+}
+
+/// Turns on ANSI escape handling for the console behind stdout. Windows Terminal
+/// has it on already; the classic console host needs to be asked.
+pub fn enable_ansi_output() -> bool {
+    use winapi::um::{
+        consoleapi::{GetConsoleMode, SetConsoleMode},
+        processenv::GetStdHandle,
+        winbase::STD_OUTPUT_HANDLE,
+        wincon::ENABLE_VIRTUAL_TERMINAL_PROCESSING,
+    };
+
+    unsafe {
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+        let mut mode = 0;
+        GetConsoleMode(handle, &mut mode) != 0
+            && SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    }
+}
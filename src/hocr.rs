@@ -0,0 +1,210 @@
+// src/hocr.rs
+// Best-effort Markdown from Tesseract hOCR output: lines noticeably taller than
+// the body text become headings, `ocr_par` blocks become paragraphs.
+
+use crate::markup;
+use regex::Regex;
+
+/// A line at least this many times the median line height is a `#` heading.
+const H1_RATIO: f64 = 2.0;
+/// A line at least this many times the median line height is a `##` heading.
+const H2_RATIO: f64 = 1.5;
+
+struct Line {
+    paragraph: usize,
+    height: f64,
+    words: Vec<String>,
+}
+
+/// Converts hOCR to Markdown headings and paragraphs.
+pub fn to_markdown(hocr: &str) -> String {
+    let lines = parse_lines(hocr);
+    let mut heights: Vec<f64> = lines
+        .iter()
+        .filter(|line| !line.words.is_empty())
+        .map(|line| line.height)
+        .collect();
+    if heights.is_empty() {
+        return String::new();
+    }
+    heights.sort_by(|a, b| a.total_cmp(b));
+    let median = heights[heights.len() / 2].max(1.0);
+
+    // (heading prefix, paragraph, text) blocks, merging consecutive lines of the same kind.
+    let mut blocks: Vec<(&str, usize, String)> = Vec::new();
+    for line in lines.iter().filter(|line| !line.words.is_empty()) {
+        let ratio = line.height / median;
+        let prefix = if ratio >= H1_RATIO {
+            "# "
+        } else if ratio >= H2_RATIO {
+            "## "
+        } else {
+            ""
+        };
+        let text = line.words.join(" ");
+        match blocks.last_mut() {
+            Some((last_prefix, paragraph, block))
+                if *last_prefix == prefix && *paragraph == line.paragraph =>
+            {
+                block.push(' ');
+                block.push_str(&text);
+            }
+            _ => blocks.push((prefix, line.paragraph, text)),
+        }
+    }
+    blocks
+        .into_iter()
+        .map(|(prefix, _, text)| format!("{}{}", prefix, text))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn parse_lines(hocr: &str) -> Vec<Line> {
+    let tag = Regex::new(
+        r#"<(?:span|p|div)\b[^>]*class=['"](ocr_par|ocr_line|ocr_header|ocr_caption|ocr_textfloat|ocrx_word)['"][^>]*>"#,
+    )
+    .expect("valid hOCR tag regex");
+    let title = Regex::new(r#"title=['"]([^'"]*)['"]"#).expect("valid hOCR title regex");
+
+    let mut lines: Vec<Line> = Vec::new();
+    let mut paragraph = 0;
+    for captures in tag.captures_iter(hocr) {
+        let whole = captures.get(0).expect("match has a full capture");
+        let props = title
+            .captures(whole.as_str())
+            .and_then(|c| c.get(1))
+            .map_or("", |m| m.as_str());
+        match &captures[1] {
+            "ocr_par" => paragraph += 1,
+            "ocrx_word" => {
+                let rest = &hocr[whole.end()..];
+                let inner = rest.find("</span>").map_or(rest, |end| &rest[..end]);
+                let word = markup::html_to_text(inner);
+                if let (Some(line), false) = (lines.last_mut(), word.is_empty()) {
+                    line.words.push(word);
+                }
+            }
+            _ => lines.push(Line {
+                paragraph,
+                height: line_height(props),
+                words: Vec::new(),
+            }),
+        }
+    }
+    lines
+}
+
+/// Text height of a line from its hOCR properties: `x_size` when present, else the bbox height.
+fn line_height(props: &str) -> f64 {
+    let property = |name: &str| {
+        props
+            .split(';')
+            .map(str::trim)
+            .find_map(|prop| prop.strip_prefix(name))
+            .map(str::trim)
+    };
+    if let Some(size) = property("x_size").and_then(|value| value.parse().ok()) {
+        return size;
+    }
+    property("bbox")
+        .map(|value| {
+            value
+                .split_whitespace()
+                .filter_map(|n| n.parse::<f64>().ok())
+                .collect::<Vec<_>>()
+        })
+        .filter(|bbox| bbox.len() == 4)
+        .map_or(0.0, |bbox| bbox[3] - bbox[1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One `ocr_par` per entry, each holding lines of `(title, words)`.
+    fn hocr(paragraphs: &[&[(&str, &[&str])]]) -> String {
+        let mut html = String::from("<div class='ocr_page'>");
+        for lines in paragraphs {
+            html.push_str("<p class='ocr_par' title='bbox 0 0 100 100'>");
+            for (title, words) in lines.iter() {
+                html.push_str(&format!("<span class='ocr_line' title='{}'>", title));
+                for word in words.iter() {
+                    html.push_str(&format!(
+                        "<span class='ocrx_word' title='bbox 0 0 1 1; x_wconf 95'>{}</span> ",
+                        word
+                    ));
+                }
+                html.push_str("</span>");
+            }
+            html.push_str("</p>");
+        }
+        html.push_str("</div>");
+        html
+    }
+
+    #[test]
+    fn x_size_takes_precedence_over_bbox() {
+        // The bbox says this line is tall, but its x_size matches the body text.
+        let page = hocr(&[
+            &[("bbox 0 0 100 60; x_size 20", &["Not", "a", "heading"])],
+            &[("bbox 0 100 100 120", &["Body"])],
+            &[("bbox 0 200 100 220", &["More"])],
+        ]);
+        assert_eq!(to_markdown(&page), "Not a heading\n\nBody\n\nMore");
+    }
+
+    #[test]
+    fn bbox_height_is_used_without_x_size() {
+        let page = hocr(&[
+            &[("bbox 0 0 100 40", &["Title"])],
+            &[("bbox 0 100 100 120", &["Body"])],
+            &[("bbox 0 200 100 220", &["More"])],
+        ]);
+        assert_eq!(to_markdown(&page), "# Title\n\nBody\n\nMore");
+    }
+
+    #[test]
+    fn heading_ratio_thresholds() {
+        // Median height 20: 40 is exactly H1_RATIO, 30 exactly H2_RATIO, 29 is body text.
+        let page = hocr(&[
+            &[("x_size 40", &["Big"])],
+            &[("x_size 30", &["Medium"])],
+            &[("x_size 29", &["Almost"])],
+            &[("x_size 20", &["Body"])],
+            &[("x_size 20", &["Text"])],
+            &[("x_size 20", &["Here"])],
+            &[("x_size 20", &["Too"])],
+        ]);
+        assert_eq!(
+            to_markdown(&page),
+            "# Big\n\n## Medium\n\nAlmost\n\nBody\n\nText\n\nHere\n\nToo"
+        );
+    }
+
+    #[test]
+    fn lines_merge_within_a_paragraph_only() {
+        let page = hocr(&[
+            &[
+                ("x_size 20", &["first", "line"]),
+                ("x_size 20", &["second", "line"]),
+            ],
+            &[("x_size 20", &["next", "paragraph"])],
+        ]);
+        assert_eq!(
+            to_markdown(&page),
+            "first line second line\n\nnext paragraph"
+        );
+    }
+
+    #[test]
+    fn words_are_entity_decoded() {
+        let page = hocr(&[&[("x_size 20", &["Tom&amp;Jerry", "&lt;3", "&quot;hi&quot;"])]]);
+        assert_eq!(to_markdown(&page), "Tom&Jerry <3 \"hi\"");
+    }
+
+    #[test]
+    fn empty_hocr_gives_empty_markdown() {
+        assert_eq!(to_markdown(""), "");
+        assert_eq!(to_markdown(&hocr(&[&[("x_size 20", &[])]])), "");
+    }
+}
@@ -0,0 +1,197 @@
+// src/image_ops.rs
+// Pixel-level preprocessing applied to images before OCR.
+
+use image::{DynamicImage, GenericImageView};
+
+/// `--color-mask <hexcolor,tolerance>`: keep only the region drawn in one color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorMask {
+    pub rgb: [u8; 3],
+    /// Largest per-channel difference that still counts as the key color.
+    pub tolerance: u8,
+}
+
+/// Parses `RRGGBB,tolerance`, with an optional leading `#`.
+pub fn parse_color_mask(spec: &str) -> Result<ColorMask, String> {
+    let (hex, tolerance) = spec
+        .split_once(',')
+        .ok_or_else(|| format!("expected '<hexcolor>,<tolerance>', got '{}'", spec))?;
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a RRGGBB hex color", hex));
+    }
+    let channel = |at: usize| u8::from_str_radix(&hex[at..at + 2], 16).unwrap();
+    let tolerance = tolerance
+        .trim()
+        .parse()
+        .map_err(|_| format!("tolerance '{}' must be 0-255", tolerance.trim()))?;
+    Ok(ColorMask {
+        rgb: [channel(0), channel(2), channel(4)],
+        tolerance,
+    })
+}
+
+/// Crops to the bounding box of the pixels matching `mask`, e.g. a highlighted box
+/// with its contents. `None` if no pixel matches.
+pub fn crop_to_color(img: &DynamicImage, mask: ColorMask) -> Option<DynamicImage> {
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    for (x, y, pixel) in img.pixels() {
+        let matches = pixel.0[..3]
+            .iter()
+            .zip(mask.rgb)
+            .all(|(&channel, key)| channel.abs_diff(key) <= mask.tolerance);
+        if matches {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+    }
+    (min_x <= max_x).then(|| img.crop_imm(min_x, min_y, max_x - min_x + 1, max_y - min_y + 1))
+}
+
+/// Mean Rec. 601 luma of the image, 0 (black) to 255 (white).
+pub fn mean_luminance(img: &DynamicImage) -> f64 {
+    let rgb = img.to_rgb8();
+    let count = rgb.width() as f64 * rgb.height() as f64;
+    if count == 0.0 {
+        return 0.0;
+    }
+    let sum: f64 = rgb
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b] = pixel.0;
+            0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64
+        })
+        .sum();
+    sum / count
+}
+
+/// `--flatten-alpha`: how transparency is handled before OCR.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FlattenAlpha {
+    /// Drop an alpha channel that is zero everywhere. Clipboard bitmaps (e.g. from
+    /// Snipping Tool) often carry one, and it makes the saved image fully transparent.
+    Auto,
+    /// Also composite any other transparency onto white.
+    Always,
+    /// Keep the alpha channel as decoded.
+    Never,
+}
+
+/// Applies `mode`, returning the flattened image and what was done, or `None` when
+/// the image is left as is.
+pub fn flatten_alpha(
+    img: &DynamicImage,
+    mode: FlattenAlpha,
+) -> Option<(DynamicImage, &'static str)> {
+    if mode == FlattenAlpha::Never || !img.color().has_alpha() {
+        return None;
+    }
+    let rgba = img.to_rgba8();
+    if rgba.pixels().all(|pixel| pixel.0[3] == 0) {
+        let opaque = DynamicImage::ImageRgb8(img.to_rgb8());
+        return Some((opaque, "dropped an all-zero alpha channel"));
+    }
+    if mode == FlattenAlpha::Auto || rgba.pixels().all(|pixel| pixel.0[3] == 255) {
+        return None;
+    }
+    let mut flattened = image::RgbImage::new(rgba.width(), rgba.height());
+    for (out, pixel) in flattened.pixels_mut().zip(rgba.pixels()) {
+        let [r, g, b, a] = pixel.0;
+        let blend =
+            |channel: u8| ((channel as u32 * a as u32 + 255 * (255 - a as u32) + 127) / 255) as u8;
+        out.0 = [blend(r), blend(g), blend(b)];
+    }
+    Some((
+        DynamicImage::ImageRgb8(flattened),
+        "composited the transparency onto white",
+    ))
+}
+
+/// Reads the declared dimensions from the image header without decoding the pixels,
+/// and rejects images with more than `max_pixels` pixels (0 allows any size).
+pub fn check_pixel_count(data: &[u8], max_pixels: u64) -> anyhow::Result<()> {
+    if max_pixels == 0 {
+        return Ok(());
+    }
+    let (width, height) = image::ImageReader::new(std::io::Cursor::new(data))
+        .with_guessed_format()?
+        .into_dimensions()
+        .map_err(|e| anyhow::anyhow!("Failed to read the image dimensions: {}", e))?;
+    let pixels = width as u64 * height as u64;
+    if pixels > max_pixels {
+        anyhow::bail!(
+            "Image is {}x{} ({} pixels), more than --max-pixels {}. Refusing to decode it.",
+            width,
+            height,
+            pixels,
+            max_pixels
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// A white 40x30 image with a yellow box from (10, 5) to (24, 19) inclusive.
+    fn highlighted_image() -> DynamicImage {
+        let mut img = RgbImage::from_pixel(40, 30, Rgb([255, 255, 255]));
+        for y in 5..20 {
+            for x in 10..25 {
+                img.put_pixel(x, y, Rgb([250, 230, 10]));
+            }
+        }
+        DynamicImage::ImageRgb8(img)
+    }
+
+    fn bounds(img: &DynamicImage, mask: ColorMask) -> Option<(u32, u32)> {
+        crop_to_color(img, mask).map(|cropped| cropped.dimensions())
+    }
+
+    #[test]
+    fn crops_to_the_colored_box() {
+        let mask = parse_color_mask("ffff00,30").unwrap();
+        assert_eq!(bounds(&highlighted_image(), mask), Some((15, 15)));
+        let cropped = crop_to_color(&highlighted_image(), mask).unwrap();
+        assert_eq!(cropped.get_pixel(0, 0).0, [250, 230, 10, 255]);
+    }
+
+    #[test]
+    fn tolerance_is_inclusive() {
+        // The box differs from pure yellow by at most 25 (green 230 vs 255).
+        let img = highlighted_image();
+        let key = [255, 255, 0];
+        let at = |tolerance| ColorMask {
+            rgb: key,
+            tolerance,
+        };
+        assert_eq!(bounds(&img, at(25)), Some((15, 15)));
+        assert_eq!(bounds(&img, at(24)), None);
+    }
+
+    #[test]
+    fn no_matching_pixel_gives_none() {
+        let mask = parse_color_mask("#0000ff,10").unwrap();
+        assert_eq!(bounds(&highlighted_image(), mask), None);
+    }
+
+    #[test]
+    fn parses_color_masks() {
+        assert_eq!(
+            parse_color_mask("#FF8000, 12"),
+            Ok(ColorMask {
+                rgb: [255, 128, 0],
+                tolerance: 12
+            })
+        );
+        assert!(parse_color_mask("ff00,10").is_err());
+        assert!(parse_color_mask("gg0000,10").is_err());
+        assert!(parse_color_mask("ff0000").is_err());
+        assert!(parse_color_mask("ff0000,256").is_err());
+        assert!(parse_color_mask("ff0000,-1").is_err());
+    }
+}
@@ -0,0 +1,433 @@
+// src/input.rs
+// Copied files, folders and URLs: picks what to OCR or transcribe, extracting
+// video audio with ffmpeg and downloading stream audio with yt-dlp on the way.
+
+use crate::{
+    pipeline::{self, Content},
+    tempfiles, transcribe,
+};
+use anyhow::{anyhow, Context, Result};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+pub const AUDIO_EXTENSIONS: &[&str] = &[
+    "wav", "mp3", "m4a", "ogg", "flac", "aac", "wma", "opus", "aiff", "aif",
+];
+pub const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "mov", "avi", "wmv", "flv", "webm", "mpeg", "mpg", "m4v", "3gp",
+];
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "gif", "tif", "tiff", "webp"];
+
+/// What to do with a single copied file whose extension isn't a known audio, video or image type.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnknownFilePolicy {
+    /// Fail with an error, as for any unsupported content.
+    Error,
+    /// Try to decode it as an image and OCR it.
+    Ocr,
+    /// Hand it to ffmpeg/the transcription service as audio.
+    Transcribe,
+    /// Do nothing and leave the clipboard as it was.
+    Skip,
+}
+
+/// Where transcribed audio came from, which picks the default transcript format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptSource {
+    Audio,
+    /// A video file or video/stream URL.
+    Video,
+    Microphone,
+}
+
+impl TranscriptSource {
+    pub fn for_path(path: &Path) -> Self {
+        if VIDEO_EXTENSIONS.contains(&lowercase_extension(path).as_str()) {
+            TranscriptSource::Video
+        } else {
+            TranscriptSource::Audio
+        }
+    }
+}
+
+/// What to do when the single copied "file" is a folder.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DirPolicy {
+    /// Fail with an error explaining that a folder was copied.
+    Error,
+    /// Process the first supported file in the folder, by name.
+    FirstFile,
+    /// Process every supported file in the folder, by name, up to `--max-files`.
+    AllFiles,
+}
+
+/// What to do with audio/video triggers when no OpenAI API key is configured.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MissingKeyPolicy {
+    /// Fail with an error sound, as for any failed trigger.
+    Error,
+    /// Work as an OCR-only tool: explain that transcription is off and leave audio alone.
+    OcrOnly,
+    /// Quietly ignore audio/video triggers.
+    Skip,
+}
+
+/// How copied files, folders and URLs are turned into recognizable content.
+#[derive(Debug, Clone)]
+pub struct InputSettings {
+    pub unknown_file_policy: UnknownFilePolicy,
+    pub dir_policy: DirPolicy,
+    /// Most files processed from one folder with `DirPolicy::AllFiles`.
+    pub max_files: usize,
+    pub on_missing_key: MissingKeyPolicy,
+    /// Only the first N seconds of a video's audio are extracted.
+    pub max_duration_secs: Option<u64>,
+    pub ytdlp_cmd: String,
+    /// Only the first N seconds of URL audio are downloaded.
+    pub ytdlp_max_duration_secs: Option<u64>,
+    /// Joins the texts of the files in a folder.
+    pub page_separator: String,
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        InputSettings {
+            unknown_file_policy: UnknownFilePolicy::Error,
+            dir_policy: DirPolicy::Error,
+            max_files: 20,
+            on_missing_key: MissingKeyPolicy::Error,
+            max_duration_secs: None,
+            ytdlp_cmd: "yt-dlp".to_string(),
+            ytdlp_max_duration_secs: None,
+            page_separator: "\n\n".to_string(),
+        }
+    }
+}
+
+/// Recognizes the single files picked out by `process_files`. A `pipeline::Config`
+/// is one; the binary wraps it to add its sounds, prompts and exports.
+pub trait Recognizer {
+    /// Whether audio can be transcribed at all, i.e. an API key or a diarization
+    /// backend is configured.
+    fn can_transcribe(&self) -> bool;
+    fn ocr_image_file(&self, path: &Path) -> Result<String>;
+    fn transcribe_audio_file(&self, path: &Path, source: TranscriptSource) -> Result<String>;
+}
+
+impl Recognizer for pipeline::Config {
+    fn can_transcribe(&self) -> bool {
+        self.openai_api_key.is_some() || self.diarize.is_some()
+    }
+
+    fn ocr_image_file(&self, path: &Path) -> Result<String> {
+        pipeline::process_content(Content::ImageFile(path.to_path_buf()), self)
+            .map(|outcome| outcome.text)
+    }
+
+    /// Transcribes in `self.transcribe.format` whatever the source.
+    fn transcribe_audio_file(&self, path: &Path, _source: TranscriptSource) -> Result<String> {
+        pipeline::process_content(Content::AudioFile(path.to_path_buf()), self)
+            .map(|outcome| outcome.text)
+    }
+}
+
+/// Applies `on_missing_key` to content that needs transcription. Returns `None` when
+/// transcription should go ahead (the recognizer can transcribe, or missing keys are
+/// reported as errors).
+pub fn missing_key_outcome(
+    settings: &InputSettings,
+    recognizer: &impl Recognizer,
+) -> Option<Result<String>> {
+    if recognizer.can_transcribe() {
+        return None;
+    }
+    match settings.on_missing_key {
+        MissingKeyPolicy::Error => None,
+        MissingKeyPolicy::OcrOnly => {
+            println!("Audio/video needs an OpenAI API key, and none is set. Running OCR-only; nothing to do.");
+            Some(Ok(String::new()))
+        }
+        MissingKeyPolicy::Skip => {
+            println!("No OpenAI API key; audio/video trigger skipped.");
+            Some(Ok(String::new()))
+        }
+    }
+}
+
+fn lowercase_extension(path: &Path) -> String {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Transcribes a single copied audio or video file, OCRs a single image file, or
+/// handles a copied folder according to `dir_policy`.
+pub fn process_files(
+    files: &[String],
+    settings: &InputSettings,
+    recognizer: &impl Recognizer,
+) -> Result<String> {
+    let [file] = files else {
+        return Err(anyhow!(
+            "Clipboard contains {} files. Only single audio/video/image file processing is supported.",
+            files.len()
+        ));
+    };
+    let file_path = PathBuf::from(file);
+    if file_path.is_dir() {
+        return process_directory(&file_path, settings, recognizer);
+    }
+    let extension = lowercase_extension(&file_path);
+
+    let needs_transcription = AUDIO_EXTENSIONS.contains(&extension.as_str())
+        || VIDEO_EXTENSIONS.contains(&extension.as_str())
+        || (!IMAGE_EXTENSIONS.contains(&extension.as_str())
+            && settings.unknown_file_policy == UnknownFilePolicy::Transcribe);
+    if needs_transcription {
+        if let Some(outcome) = missing_key_outcome(settings, recognizer) {
+            return outcome;
+        }
+    }
+
+    if AUDIO_EXTENSIONS.contains(&extension.as_str()) {
+        println!("Detected single audio file: {:?}", file_path);
+        recognizer.transcribe_audio_file(&file_path, TranscriptSource::Audio)
+    } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+        println!(
+            "Detected single video file: {:?}. Extracting audio...",
+            file_path
+        );
+        let temp_audio_file = extract_video_audio(&file_path, settings)?;
+        recognizer.transcribe_audio_file(temp_audio_file.path(), TranscriptSource::Video)
+        // The extracted audio is removed here.
+    } else if IMAGE_EXTENSIONS.contains(&extension.as_str()) {
+        println!("Detected single image file: {:?}", file_path);
+        recognizer.ocr_image_file(&file_path)
+    } else {
+        match settings.unknown_file_policy {
+            UnknownFilePolicy::Error => Err(anyhow!(
+                "Clipboard contains a single file, but it's not a supported audio, video or image format (Checked extensions: {:?}, {:?}, {:?}, Found: {}).",
+                AUDIO_EXTENSIONS, VIDEO_EXTENSIONS, IMAGE_EXTENSIONS, extension
+            )),
+            UnknownFilePolicy::Ocr => {
+                println!(
+                    "Unknown file type '{}'. Trying OCR as an image...",
+                    extension
+                );
+                recognizer.ocr_image_file(&file_path)
+            }
+            UnknownFilePolicy::Transcribe => {
+                println!(
+                    "Unknown file type '{}'. Trying transcription as audio...",
+                    extension
+                );
+                recognizer.transcribe_audio_file(&file_path, TranscriptSource::Audio)
+            }
+            UnknownFilePolicy::Skip => {
+                println!(
+                    "Unknown file type '{}'. Skipping (--unknown-file-policy skip).",
+                    extension
+                );
+                Ok(String::new())
+            }
+        }
+    }
+}
+
+/// Extracts the audio track of a video into a temporary mp3 via ffmpeg, truncated
+/// to `max_duration_secs`.
+fn extract_video_audio(
+    video_path: &Path,
+    settings: &InputSettings,
+) -> Result<tempfile::NamedTempFile> {
+    println!("INFO: Video processing requires ffmpeg in PATH.");
+    // The extracted audio track is smaller than the video holding it.
+    tempfiles::ensure_free_space(transcribe::trans::file_len(video_path))?;
+    let temp_audio_file = tempfiles::file("extracted_audio", ".mp3")?;
+    println!(
+        "Extracting audio via ffmpeg to temporary file: {:?}",
+        temp_audio_file.path()
+    );
+    let mut ffmpeg_command = Command::new("ffmpeg");
+    ffmpeg_command.arg("-i").arg(video_path);
+    if let Some(secs) = settings.max_duration_secs {
+        println!("Truncating extracted audio to the first {} seconds.", secs);
+        ffmpeg_command.arg("-t").arg(secs.to_string());
+    }
+    let ffmpeg_output = ffmpeg_command
+        .arg("-vn")
+        .arg("-q:a")
+        .arg("0")
+        .arg("-y")
+        .arg(temp_audio_file.path())
+        .output()
+        .with_context(|| "Failed to execute ffmpeg command. Is ffmpeg installed and in PATH?")?;
+
+    if !ffmpeg_output.status.success() {
+        let stderr = String::from_utf8_lossy(&ffmpeg_output.stderr);
+        return Err(anyhow!(
+            "ffmpeg failed to extract audio (Status: {}):\n{}",
+            ffmpeg_output.status,
+            stderr
+        ));
+    }
+    transcribe::trans::ensure_nonempty_audio(temp_audio_file.path(), video_path)?;
+    println!("Audio extraction successful.");
+    Ok(temp_audio_file)
+}
+
+/// Handles a copied folder according to `dir_policy`.
+pub fn process_directory(
+    dir: &Path,
+    settings: &InputSettings,
+    recognizer: &impl Recognizer,
+) -> Result<String> {
+    if settings.dir_policy == DirPolicy::Error {
+        return Err(anyhow!(
+            "Clipboard contains a folder ({:?}), not a file. Copy a file inside it, or use --dir-policy first-file|all-files.",
+            dir
+        ));
+    }
+
+    let mut supported_files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read folder {:?}", dir))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file() && is_supported_file(path))
+        .collect();
+    supported_files.sort();
+    if supported_files.is_empty() {
+        return Err(anyhow!(
+            "Folder {:?} contains no supported audio, video or image files.",
+            dir
+        ));
+    }
+
+    if settings.dir_policy == DirPolicy::FirstFile {
+        println!(
+            "Copied a folder. Processing its first supported file: {:?}",
+            supported_files[0]
+        );
+        return process_files(
+            &[supported_files[0].to_string_lossy().into_owned()],
+            settings,
+            recognizer,
+        );
+    }
+    if supported_files.len() > settings.max_files {
+        println!(
+            "Folder has {} supported files. Processing only the first {} (--max-files).",
+            supported_files.len(),
+            settings.max_files
+        );
+        supported_files.truncate(settings.max_files);
+    }
+    let mut texts = Vec::with_capacity(supported_files.len());
+    for (index, path) in supported_files.iter().enumerate() {
+        println!(
+            "[{}/{}] Processing {:?}",
+            index + 1,
+            supported_files.len(),
+            path
+        );
+        let text = process_files(&[path.to_string_lossy().into_owned()], settings, recognizer)?;
+        texts.push(text.trim().to_string());
+    }
+    Ok(texts.join(&settings.page_separator))
+}
+
+pub fn is_supported_file(path: &Path) -> bool {
+    let extension = lowercase_extension(path);
+    [AUDIO_EXTENSIONS, VIDEO_EXTENSIONS, IMAGE_EXTENSIONS]
+        .iter()
+        .any(|extensions| extensions.contains(&extension.as_str()))
+}
+
+/// Returns the URL if the text is exactly one http(s) URL.
+pub fn as_single_url(text: &str) -> Option<&str> {
+    let trimmed = text.trim();
+    let is_url = trimmed.starts_with("http://") || trimmed.starts_with("https://");
+    if is_url && !trimmed.contains(char::is_whitespace) {
+        Some(trimmed)
+    } else {
+        None
+    }
+}
+
+/// Downloads the audio of a video/stream URL and transcribes it.
+pub fn transcribe_url(
+    url: &str,
+    settings: &InputSettings,
+    recognizer: &impl Recognizer,
+) -> Result<String> {
+    if let Some(outcome) = missing_key_outcome(settings, recognizer) {
+        return outcome;
+    }
+    let download_dir = tempfiles::dir("stream_audio")?;
+    let audio_path = download_stream_audio(url, settings, download_dir.path())?;
+    recognizer.transcribe_audio_file(&audio_path, TranscriptSource::Video)
+    // The download directory is removed here.
+}
+
+/// Downloads the audio track of a video/stream URL as mp3 into `output_dir` via yt-dlp.
+fn download_stream_audio(
+    url: &str,
+    settings: &InputSettings,
+    output_dir: &Path,
+) -> Result<PathBuf> {
+    println!(
+        "Downloading audio from {} via {}...",
+        url, settings.ytdlp_cmd
+    );
+    let mut command = Command::new(&settings.ytdlp_cmd);
+    command
+        .arg("--no-playlist")
+        .arg("--extract-audio")
+        .arg("--audio-format")
+        .arg("mp3")
+        .arg("--output")
+        .arg(output_dir.join("stream_audio.%(ext)s"));
+    if let Some(max_secs) = settings.ytdlp_max_duration_secs {
+        println!("Limiting download to the first {} seconds.", max_secs);
+        command
+            .arg("--download-sections")
+            .arg(format!("*0-{}", max_secs));
+    }
+    command.arg(url);
+
+    let output = command.output().map_err(|err| {
+        if err.kind() == std::io::ErrorKind::NotFound {
+            anyhow!(
+                "yt-dlp command '{}' not found. Please install yt-dlp and ensure it is in your PATH.",
+                settings.ytdlp_cmd
+            )
+        } else {
+            anyhow!(
+                "Failed to execute yt-dlp command '{}': {}",
+                settings.ytdlp_cmd,
+                err
+            )
+        }
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!(
+            "yt-dlp failed to download audio (Status: {}):\n{}",
+            output.status,
+            stderr
+        ));
+    }
+
+    let audio_path = output_dir.join("stream_audio.mp3");
+    if !audio_path.exists() {
+        return Err(anyhow!(
+            "yt-dlp reported success but no audio file was written to {:?}",
+            audio_path
+        ));
+    }
+    println!("Audio download successful: {:?}", audio_path);
+    Ok(audio_path)
+}
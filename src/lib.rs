@@ -0,0 +1,23 @@
+// src/lib.rs
+// The recognition pipeline as a library, for embedding OCR and transcription
+// without the global hotkey and clipboard machinery of the `ocrp` binary.
+
+pub mod annotate;
+pub mod cache;
+pub mod cli;
+pub mod diarize;
+pub mod hocr;
+pub mod image_ops;
+pub mod input;
+pub mod markup;
+pub mod ocr;
+pub mod pipeline;
+pub mod tempfiles;
+pub mod text_transform;
+pub mod tiff_pages;
+pub mod transcribe;
+
+pub use pipeline::{
+    process_content, process_content_with_progress, Config, Content, ContentKind, NoProgress,
+    OcrSettings, ProcessOutcome, ProgressCallback, Transcriber,
+};
@@ -3,41 +3,107 @@
 use anyhow::{anyhow, Context as AnyhowContext, Result};
 use clap::Parser;
 use clipboard_win::{formats, get_clipboard, Clipboard, Setter};
-use dotenvy;
 // Use winapi import
 use winapi::um::utilapiset::Beep;
 
-use image::ImageFormat;
-use rdev::{listen, simulate, Event, EventType, Key};
+use rdev::{listen, Event, EventType};
 use std::{
+    borrow::Cow,
     env,
-    path::PathBuf,
-    process::Command,
+    path::{Path, PathBuf},
     sync::mpsc::{self, Receiver, Sender},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+    },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
-use tempfile::Builder as TempFileBuilder;
 
+use input::TranscriptSource;
+use ocrp::{
+    annotate, cli::PipelineArgs, image_ops, input, markup, ocr, pipeline, tempfiles,
+    text_transform, transcribe,
+};
+use transcribe::trans::TranscriptFormat;
+mod archive;
+mod config;
+mod control_event;
+mod diagnose;
+use config::Config;
 mod easy_rdev_key;
+mod highlight;
 use easy_rdev_key::PTTKey;
-mod transcribe;
+mod output_file;
+use output_file::OutputEncoding;
+mod paste;
+mod record;
+mod run_log;
+mod self_test;
+mod settings_gui;
+mod simulate_clipboard;
+mod uia;
+mod webhook;
+use paste::{PasteMethod, PasteSequenceVariant};
+use text_transform::{ReplaceRule, TextCase, TrimMode};
+mod window_info;
+use window_info::DpiAwareness;
 
 use async_openai::{config::OpenAIConfig, Client};
 use default_device_sink::DefaultDeviceSink;
+use notify::{RecursiveMode, Watcher};
 use rodio::source::{SineWave, Source};
 use rodio::Decoder;
 use std::io::{BufReader, Cursor};
 use tokio::runtime::Runtime;
 
 // --- Constants ---
-const AUDIO_EXTENSIONS: &[&str] = &[
-    "wav", "mp3", "m4a", "ogg", "flac", "aac", "wma", "opus", "aiff", "aif",
-];
-const VIDEO_EXTENSIONS: &[&str] = &[
-    "mp4", "mkv", "mov", "avi", "wmv", "flv", "webm", "mpeg", "mpg", "m4v", "3gp",
-];
 const CLIPBRD_E_UNSUPPORTEDFORMAT: i32 = -2147221040;
+/// Pause before re-reading the clipboard after an empty result (`--retry-on-empty`).
+const RETRY_ON_EMPTY_DELAY_MS: u64 = 300;
+
+/// What to do when the paste target is a password field.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum PasswordFieldPolicy {
+    /// Paste as usual.
+    Allow,
+    /// Paste, but log a warning.
+    Warn,
+    /// Don't paste: leave the text on the clipboard and log a warning.
+    Skip,
+}
+
+/// What `--output-file` receives for OCR. Non-text formats come from a second
+/// Tesseract pass with the matching config file; the plain text is still pasted.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OcrOutputFormat {
+    /// The recognized text, appended per run.
+    Text,
+    Hocr,
+    Alto,
+    Tsv,
+}
+
+impl OcrOutputFormat {
+    /// Name of the Tesseract config file that selects this output.
+    fn tesseract_config(self) -> Option<&'static str> {
+        match self {
+            OcrOutputFormat::Text => None,
+            OcrOutputFormat::Hocr => Some("hocr"),
+            OcrOutputFormat::Alto => Some("alto"),
+            OcrOutputFormat::Tsv => Some("tsv"),
+        }
+    }
+}
+
+/// When the `--beeps` success sound plays relative to the paste.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum SuccessSoundTiming {
+    /// Right after the paste keystrokes, before the clipboard is restored.
+    AfterPaste,
+    /// Once the original clipboard is back, when the trigger has fully finished.
+    AfterRestore,
+}
 
 // --- Args Struct ---
 #[derive(Parser, Debug, Clone)]
@@ -48,26 +114,465 @@ const CLIPBRD_E_UNSUPPORTEDFORMAT: i32 = -2147221040;
     long_about = "Listens for a key press, processes clipboard content (image OCR or audio transcription), pastes text, and restores original clipboard."
 )]
 struct Args {
-    #[arg(short, long, value_enum, help = "Key to trigger processing.")]
-    trigger_key: PTTKey,
-    #[arg(
-        short = 'l',
-        long,
-        default_value = "eng",
-        help = "Tesseract language code(s)."
-    )]
-    lang: String,
-    #[arg(long, default_value = "tesseract", help = "Tesseract command/path.")]
-    tesseract_cmd: String,
-    #[arg(long, help = "Path to Tesseract data directory.")]
-    tessdata_path: Option<String>,
-    #[arg(long, help = "Additional Tesseract CLI args.", num_args = 0..)]
-    tesseract_args: Vec<String>,
-    #[arg(long, help = "OpenAI API Key (overrides .env/env var).")]
-    openai_api_key: Option<String>,
+    #[arg(
+        short,
+        long,
+        value_enum,
+        help = "Key to trigger processing (or set trigger_key in the config file)."
+    )]
+    trigger_key: Option<PTTKey>,
+    #[arg(
+        long,
+        value_enum,
+        help = "Key that pastes the last recognized text again, without re-running OCR or transcription."
+    )]
+    repeat_key: Option<PTTKey>,
+    #[command(flatten)]
+    pipeline: PipelineArgs,
+    #[arg(
+        long = "replace",
+        value_parser = text_transform::parse_replace_rule,
+        help = "Regex replacement '<regex>=><replacement>' applied to the text (repeatable, in order)."
+    )]
+    replace_rules: Vec<ReplaceRule>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TextCase::None,
+        help = "Letter case applied to the recognized text before pasting."
+    )]
+    case: TextCase,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = TrimMode::Full,
+        help = "Whitespace removed around the text: all of it (full), only blank leading/trailing lines, keeping indentation (lines), or none."
+    )]
+    trim_mode: TrimMode,
+    #[arg(
+        long,
+        help = "Replace typographic quotes, dashes, special spaces and ligatures (e.g. \u{201C}, \u{2014}, \u{FB01}) with plain ASCII. See README for the full table."
+    )]
+    ascii_normalize: bool,
+    #[arg(
+        long,
+        help = "Replace line breaks with a literal '\\n' before pasting, for single-line fields (e.g. chat boxes) where Enter submits."
+    )]
+    escape_newlines: bool,
+    #[arg(
+        long,
+        default_value_t = 3,
+        help = "Extra rounds to wait for the clipboard when another app holds it."
+    )]
+    clipboard_retry_rounds: u32,
+    #[arg(
+        long,
+        default_value_t = 500,
+        help = "Delay in ms between clipboard retry rounds."
+    )]
+    clipboard_retry_delay_ms: u64,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Re-read the clipboard and re-process up to N times when the recognized text is empty (e.g. the copy hadn't finished yet)."
+    )]
+    retry_on_empty: u32,
+    #[arg(
+        long,
+        help = "Save a copy of the OCR input with Tesseract's word boxes drawn on it (debugging aid; bypasses the cache)."
+    )]
+    annotate_output: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Write each recognized word's text, box and confidence as JSON to this path (bypasses the cache)."
+    )]
+    boxes_json: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value = tempfiles::DEFAULT_PREFIX,
+        help = "Prefix for the temporary files OCRP creates in %TEMP%\\ocrp."
+    )]
+    temp_prefix: String,
+    #[arg(
+        long,
+        default_value_t = 86400,
+        help = "At startup, remove files in %TEMP%\\ocrp older than this many seconds left by crashed runs (0 disables)."
+    )]
+    temp_sweep_age_secs: u64,
+    #[arg(
+        long,
+        default_value_t = 100,
+        help = "Free space (MB) to keep in the temp directory. Checked at startup and before each temp file, image or audio conversion, with the conversion's estimated size added (0 disables)."
+    )]
+    min_free_mb: u64,
+    #[arg(
+        long,
+        default_value_t = 0.006,
+        help = "Transcription price per audio minute, in dollars, for cost estimates."
+    )]
+    cost_per_minute: f64,
+    #[arg(
+        long,
+        help = "Print the text to stdout instead of pasting it; the clipboard is left untouched."
+    )]
+    no_paste: bool,
+    #[arg(
+        long,
+        help = "Syntax-highlight code printed to the terminal (--no-paste, --ocr-file, --transcribe-file). The language is guessed; pasted text is unaffected."
+    )]
+    highlight: bool,
+    #[arg(
+        long,
+        help = "Save every triggered clipboard bitmap as received, plus a decoded PNG and a JSON manifest with the OCR result, to this directory."
+    )]
+    archive_bitmaps: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Also append every recognized text to this file, one entry per run."
+    )]
+    output_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OutputEncoding::Utf8,
+        help = "Text encoding for --output-file. A BOM is written only when the file is created."
+    )]
+    output_encoding: OutputEncoding,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = OcrOutputFormat::Text,
+        help = "What --output-file receives for OCR. 'hocr', 'alto' and 'tsv' overwrite it with Tesseract's structured output for the latest image and need --output-file."
+    )]
+    ocr_output_format: OcrOutputFormat,
+    #[arg(
+        long,
+        help = "Append one JSON line per run (time, source, content kind, result length, duration, error) to this file. The text itself is not logged."
+    )]
+    log_file: Option<PathBuf>,
+    #[arg(
+        long,
+        default_value_t = 1_048_576,
+        help = "Rotate --log-file to '<path>.1' once it would grow past this many bytes (0 disables rotation)."
+    )]
+    log_max_bytes: u64,
+    #[arg(
+        long,
+        help = "POST each recognized text as JSON ({text, kind, source, timestamp}) to this URL, e.g. a Slack webhook. Runs in the background; failures only warn."
+    )]
+    webhook_url: Option<String>,
+    #[arg(
+        long,
+        default_value_t = 10,
+        help = "Seconds before a --webhook-url request is abandoned."
+    )]
+    webhook_timeout: u64,
+    #[arg(
+        long,
+        help = "Exit (code 0) after this many seconds without a handled trigger, interval run or watched clipboard image."
+    )]
+    idle_exit_secs: Option<u64>,
+    #[arg(
+        long,
+        help = "When the clipboard holds only plain text (not a URL), paste it as is instead of failing."
+    )]
+    text_passthrough: bool,
+    #[arg(
+        long,
+        help = "Automatically OCR every new image copied to the clipboard and leave the text there (no paste). The trigger key becomes optional."
+    )]
+    watch_clipboard: bool,
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Also process the clipboard every N seconds, as if the trigger key were pressed. Unchanged clipboard content is skipped. The trigger key becomes optional."
+    )]
+    interval_secs: Option<u64>,
+    #[arg(
+        long,
+        default_value_t = 1800,
+        help = "Refuse to transcribe audio longer than this many seconds (0 disables the limit)."
+    )]
+    max_audio_duration_secs: u64,
+    #[arg(
+        long,
+        help = "Ask with a Yes/No dialog instead of refusing audio over --max-audio-duration-secs."
+    )]
+    confirm_long_audio: bool,
+    #[arg(
+        long,
+        help = "Verify the OpenAI API key at startup with a cheap request."
+    )]
+    check_key: bool,
+    #[arg(
+        long,
+        help = "Exit at startup if the OpenAI API key is missing or invalid."
+    )]
+    require_key: bool,
     // --- Added Beeps Flag ---
     #[arg(long, help = "Enable start and success notification beeps.")]
     beeps: bool,
+    #[arg(
+        long,
+        help = "Never play any sound (beeps, ticks, failure sound), e.g. on headless servers."
+    )]
+    no_sound: bool,
+    #[arg(
+        long,
+        default_value_t = 500,
+        help = "Skip a notification sound if the same one played less than this many ms ago (0 plays every one)."
+    )]
+    sound_min_interval_ms: u64,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = PasteSequenceVariant::Standard,
+        help = "Key press/release ordering used to simulate the paste."
+    )]
+    paste_sequence_variant: PasteSequenceVariant,
+    #[arg(
+        long,
+        default_value_t = 150,
+        help = "Delay in ms around the simulated paste for the clipboard to settle."
+    )]
+    paste_delay_ms: u64,
+    #[arg(
+        long,
+        default_value_t = 5,
+        help = "Delay in ms after each character when typing the text (paste methods 'type' and 'bracketed'). Raise it for apps that drop characters."
+    )]
+    type_delay_ms: u64,
+    #[arg(
+        long,
+        default_value_t = 0,
+        help = "Wait this many ms after the trigger before reading the clipboard, so a copy made just before it can finish."
+    )]
+    pre_read_delay_ms: u64,
+    #[arg(
+        long,
+        default_value_t = 3,
+        help = "Times to retry a simulated key event that fails (e.g. on a busy system)."
+    )]
+    simulate_retries: u32,
+    #[arg(long, help = "Press Enter in the target app before pasting.")]
+    newline_before_paste: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = PasteMethod::Clipboard,
+        help = "How text is delivered when no config paste-target rule matches."
+    )]
+    paste_method: PasteMethod,
+    #[arg(
+        long,
+        value_delimiter = ',',
+        help = "Ignore the trigger while the foreground app's exe name or title contains any of these (comma-separated)."
+    )]
+    ignore_apps: Vec<String>,
+    #[arg(
+        long,
+        help = "Skip the trigger unless the focused control shows a text caret (accepts input)."
+    )]
+    require_editable: bool,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = PasswordFieldPolicy::Warn,
+        help = "What to do when the focused field is a password field, whose contents password managers may record."
+    )]
+    password_field_policy: PasswordFieldPolicy,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = SuccessSoundTiming::AfterRestore,
+        help = "When the --beeps success sound plays. Sounds never block the paste either way."
+    )]
+    success_sound_timing: SuccessSoundTiming,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = DpiAwareness::PerMonitor,
+        help = "DPI awareness set at startup. 'per-monitor' keeps pixel coordinates physical on mixed-DPI multi-monitor setups."
+    )]
+    dpi_awareness: DpiAwareness,
+    #[arg(
+        long,
+        help = "Tap the trigger key (released within this many ms) to process the clipboard; hold it longer to record and transcribe the microphone."
+    )]
+    hold_threshold_ms: Option<u64>,
+    #[arg(
+        long,
+        default_value_t = 3,
+        value_parser = clap::value_parser!(u64).range(1..),
+        help = "Most trigger presses to queue while the worker is busy; extra presses are dropped."
+    )]
+    max_queue: u64,
+    #[arg(
+        long,
+        default_value = control_event::DEFAULT_EVENT_NAME,
+        help = "Windows named event that pauses/resumes the listener each time it is signaled."
+    )]
+    toggle_event: String,
+    #[arg(
+        long,
+        help = "Signal --toggle-event to pause/resume a running instance, then exit."
+    )]
+    signal_toggle: bool,
+    #[arg(
+        long,
+        help = "Check sounds, clipboard, input simulation, Tesseract/ffmpeg/yt-dlp and the OpenAI key, print a pass/fail checklist, then exit."
+    )]
+    self_test: bool,
+    #[arg(
+        long,
+        help = "Run OCR on an image file and print diagnostics (format, size, DPI, Tesseract command, output, timing, confidence) for bug reports, then exit."
+    )]
+    diagnose_image: Option<PathBuf>,
+    #[arg(
+        long,
+        hide = true,
+        help = "Debugging aid: run the pipeline on synthetic clipboard content ('png:<image file>', 'filelist:<path>[;<path>...]' or 'text:<text>') and report what would be pasted and restored, without touching the clipboard."
+    )]
+    simulate_clipboard: Option<String>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "OCR the given image file, print the text to stdout, then exit."
+    )]
+    ocr_file: Option<PathBuf>,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Transcribe the given audio/video file, print the text to stdout, then exit."
+    )]
+    transcribe_file: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "With --ocr-file or --transcribe-file, also copy the text to the clipboard."
+    )]
+    copy: bool,
+    #[arg(
+        long,
+        help = "Open the settings window to edit the config file, then optionally start."
+    )]
+    settings: bool,
+    #[arg(
+        long,
+        help = "Path to the TOML config file (default: %APPDATA%\\ocrp\\config.toml if present)."
+    )]
+    config: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Return focus to the target window right before the simulated paste, in case clipboard writes moved it."
+    )]
+    reassert_focus: bool,
+    #[arg(
+        long,
+        help = "Before restoring the clipboard, check that it still holds the pasted text and the target window kept focus, and warn when the paste may not have landed."
+    )]
+    verify_paste: bool,
+    #[arg(
+        long,
+        help = "Restore the original clipboard even if another app changed it while processing."
+    )]
+    force_restore: bool,
+    #[arg(
+        long,
+        help = "Also place ANSI (CF_TEXT) and HTML versions of the text on the clipboard when pasting, for apps that only read those."
+    )]
+    extra_clipboard_formats: bool,
+    #[arg(
+        long,
+        help = "When the clipboard holds both files and a bitmap, process both and paste the labeled results together."
+    )]
+    combine_when_both: bool,
+    #[arg(
+        long,
+        help = "When the clipboard holds HTML or RTF text, paste its plain text instead of running OCR."
+    )]
+    prefer_clipboard_text: bool,
+    /// Loaded from the config file at startup (and on change).
+    #[arg(skip)]
+    file_config: Config,
+}
+
+impl Args {
+    /// Overrides options with the values set in the loaded config file.
+    fn apply_file_config(&mut self) {
+        let config = self.file_config.clone();
+        if let Some(trigger_key) = config.trigger_key {
+            self.trigger_key = Some(trigger_key);
+        }
+        if let Some(lang) = config.lang {
+            self.pipeline.lang = lang;
+        }
+        if let Some(psm) = config.psm {
+            self.pipeline.psm = Some(psm);
+        }
+        if let Some(beeps) = config.beeps {
+            self.beeps = beeps;
+        }
+        if let Some(paste_delay_ms) = config.paste_delay_ms {
+            self.paste_delay_ms = paste_delay_ms;
+        }
+        if let Some(transcribe_model) = config.transcribe_model {
+            self.pipeline.transcribe_model = transcribe_model;
+        }
+    }
+
+    /// Tesseract command and tessdata directory for `--lang`, after any per-language
+    /// `[[tesseract_overrides]]` from the config file.
+    fn tesseract_engine(&self) -> (&str, Option<&str>) {
+        let pipeline = &self.pipeline;
+        let global = (
+            pipeline.tesseract_cmd.as_str(),
+            pipeline.tessdata_path.as_deref(),
+        );
+        let overrides = &self.file_config.tesseract_overrides;
+        match config::find_tesseract_override(overrides, &pipeline.lang) {
+            Some(rule) => (
+                rule.tesseract_cmd.as_deref().unwrap_or(global.0),
+                rule.tessdata_path.as_deref().or(global.1),
+            ),
+            None => global,
+        }
+    }
+
+    /// The pipeline settings, with the Tesseract engine from `tesseract_engine`.
+    /// `--transcribe-format`, else the config default for `source`. Subtitles fall back
+    /// to text when splitting, per-channel or confidence filtering is on.
+    fn transcript_format(&self, source: TranscriptSource) -> TranscriptFormat {
+        let pipeline = &self.pipeline;
+        if pipeline.word_timestamps || pipeline.diarize_backend.is_some() {
+            // The word table or speaker lines replace the transcript.
+            return TranscriptFormat::Text;
+        }
+        let defaults = &self.file_config.transcribe_formats;
+        let format = pipeline.transcribe_format.unwrap_or(match source {
+            TranscriptSource::Audio => defaults.audio.unwrap_or(TranscriptFormat::Text),
+            TranscriptSource::Video => defaults.video.unwrap_or(TranscriptFormat::Srt),
+            TranscriptSource::Microphone => defaults.microphone.unwrap_or(TranscriptFormat::Text),
+        });
+        let text_only = pipeline.split_on_silence
+            || pipeline.per_channel
+            || pipeline.min_speech_confidence.is_some();
+        if format != TranscriptFormat::Text && text_only {
+            println!(
+                "Warning: {:?} output needs a single unfiltered request; transcribing as text.",
+                format
+            );
+            return TranscriptFormat::Text;
+        }
+        format
+    }
+
+    fn pipeline_config(&self) -> pipeline::Config {
+        let mut config = self.pipeline.config();
+        let (tesseract_cmd, tessdata_path) = self.tesseract_engine();
+        config.ocr.tesseract_cmd = tesseract_cmd.to_string();
+        config.ocr.tessdata_path = tessdata_path.map(str::to_string);
+        config
+    }
 }
 
 // --- ClipboardContent Enum ---
@@ -75,385 +580,1596 @@ struct Args {
 enum ClipboardContent {
     Bitmap(Vec<u8>),
     FileList(Vec<String>),
+    /// Files and a bitmap placed together by the same copy (`--combine-when-both`).
+    FileListAndBitmap {
+        files: Vec<String>,
+        bitmap: Vec<u8>,
+    },
+    Text(String),
+    /// HTML or RTF clipboard data, kept raw for restoring, plus its extracted plain text.
+    RichText {
+        format: u32,
+        raw: Vec<u8>,
+        text: String,
+    },
+}
+
+impl ClipboardContent {
+    /// Every image carried by this content, in order. The Windows clipboard holds at
+    /// most one bitmap per format, so a `Bitmap` is always a single entry.
+    fn images(&self) -> Vec<&[u8]> {
+        match self {
+            ClipboardContent::Bitmap(data)
+            | ClipboardContent::FileListAndBitmap { bitmap: data, .. } => vec![data.as_slice()],
+            ClipboardContent::FileList(_)
+            | ClipboardContent::Text(_)
+            | ClipboardContent::RichText { .. } => Vec::new(),
+        }
+    }
+
+    /// Short label for `--log-file` records.
+    fn kind(&self) -> &'static str {
+        match self {
+            ClipboardContent::Bitmap(_) => "image",
+            ClipboardContent::FileList(_) => "files",
+            ClipboardContent::FileListAndBitmap { .. } => "files+image",
+            ClipboardContent::Text(text) if input::as_single_url(text).is_some() => "url",
+            ClipboardContent::Text(_) => "text",
+            ClipboardContent::RichText { .. } => "rich-text",
+        }
+    }
+
+    /// One-line summary of what a restore would write back, for `--simulate-clipboard`.
+    fn describe(&self) -> String {
+        match self {
+            ClipboardContent::Bitmap(data) => format!("Bitmap ({} bytes)", data.len()),
+            ClipboardContent::FileList(files) => format!("FileList {:?}", files),
+            ClipboardContent::FileListAndBitmap { files, bitmap } => {
+                format!("FileList {:?} and Bitmap ({} bytes)", files, bitmap.len())
+            }
+            ClipboardContent::Text(text) => format!("Text ({} chars)", text.chars().count()),
+            ClipboardContent::RichText { format, raw, text } => format!(
+                "Rich text (format {}, {} bytes) with plain text ({} chars)",
+                format,
+                raw.len(),
+                text.chars().count()
+            ),
+        }
+    }
 }
 
 // --- Sound Type Enum ---
+#[derive(Clone, Copy)]
 enum SoundType {
     Start,
     Success,
     Error,
+    Waiting,
 }
 
 // --- Helper: Play Sound (Windows Version) ---
-fn play_sound(sound: SoundType) {
-    let (freq_hz, dur_ms) = match sound {
+/// Beep frequency (Hz) and duration (ms) for each sound.
+fn tone(sound: &SoundType) -> (u32, u32) {
+    match sound {
         SoundType::Start => (880, 150),    // A5
         SoundType::Success => (1047, 300), // C6 (rounded)
         SoundType::Error => (262, 500),    // C4 (rounded)
-    };
-    unsafe {
-        // Beep returns 0 on failure, non-zero on success. We ignore the result.
-        let _ = Beep(freq_hz, dur_ms);
+        SoundType::Waiting => (659, 80),   // E5 (rounded)
     }
-    // Small delay to prevent sounds overlapping if triggered quickly
-    thread::sleep(Duration::from_millis(50));
+}
+
+/// `--sound-min-interval-ms`, set once at startup.
+static SOUND_MIN_INTERVAL_MS: AtomicU64 = AtomicU64::new(0);
+/// When each `SoundType` last played, indexed by its discriminant.
+static LAST_PLAYED: Mutex<[Option<Instant>; 4]> = Mutex::new([None; 4]);
+/// Held while a tone plays, so sounds fired in quick succession queue up instead of overlapping.
+static SOUND_PLAYING: Mutex<()> = Mutex::new(());
+
+/// Plays the sound on a detached thread; `Beep` blocks for the tone's whole duration,
+/// which would otherwise delay the paste flow.
+fn play_sound(sound: SoundType) {
+    if SOUND_DISABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let min_interval = Duration::from_millis(SOUND_MIN_INTERVAL_MS.load(Ordering::Relaxed));
+    {
+        let mut last_played = LAST_PLAYED.lock().expect("sound lock poisoned");
+        let last = &mut last_played[sound as usize];
+        if last.is_some_and(|played_at| played_at.elapsed() < min_interval) {
+            return; // Coalesce rapid repeats, e.g. a burst of failing triggers.
+        }
+        *last = Some(Instant::now());
+    }
+    let (freq_hz, dur_ms) = tone(&sound);
+    thread::spawn(move || {
+        let _playing = SOUND_PLAYING.lock().unwrap_or_else(|e| e.into_inner());
+        unsafe {
+            // Beep returns 0 on failure, non-zero on success. We ignore the result.
+            let _ = Beep(freq_hz, dur_ms);
+        }
+        // Small gap so back-to-back sounds stay distinguishable
+        thread::sleep(Duration::from_millis(50));
+    });
 }
 
 // --- Audio Helpers ---
 static TICK_BYTES: &[u8] = include_bytes!("../assets/tick.mp3");
 static FAILED_BYTES: &[u8] = include_bytes!("../assets/failed.mp3");
+/// `tick.mp3` / `failed.mp3` from the config directory, when present and decodable.
+static TICK_OVERRIDE: OnceLock<Vec<u8>> = OnceLock::new();
+static FAILED_OVERRIDE: OnceLock<Vec<u8>> = OnceLock::new();
 
-fn tick_loop(stop_rx: mpsc::Receiver<()>) {
-    let tick_sink = DefaultDeviceSink::new();
-    loop {
-        if stop_rx.try_recv().is_ok() {
-            tick_sink.stop();
-            break;
-        }
-        if tick_sink.empty() {
-            let cursor = Cursor::new(TICK_BYTES);
-            if let Ok(decoder) = Decoder::new(BufReader::new(cursor)) {
-                tick_sink.stop();
-                tick_sink.append(decoder);
-            } else {
-                tick_sink.stop();
-                tick_sink.append(
-                    SineWave::new(880.0)
-                        .take_duration(Duration::from_millis(50))
-                        .amplify(0.20),
-                );
-            }
+/// Picks up custom sounds dropped into `dir`. Files that don't decode are ignored
+/// with a warning, keeping the built-in sound.
+fn load_sound_overrides(dir: &Path) {
+    for (name, slot) in [
+        ("tick.mp3", &TICK_OVERRIDE),
+        ("failed.mp3", &FAILED_OVERRIDE),
+    ] {
+        let path = dir.join(name);
+        let Ok(bytes) = std::fs::read(&path) else {
+            continue;
+        };
+        if Decoder::new(BufReader::new(Cursor::new(bytes.clone()))).is_ok() {
+            println!("Using custom sound {:?}", path);
+            let _ = slot.set(bytes);
+        } else {
+            eprintln!(
+                "Warning: Could not decode {:?}; using the built-in sound.",
+                path
+            );
+        }
+    }
+}
+
+/// Decodes the custom sound if one was loaded, else the embedded default.
+fn decode_sound(
+    custom: &'static OnceLock<Vec<u8>>,
+    embedded: &'static [u8],
+) -> Option<Decoder<BufReader<Cursor<&'static [u8]>>>> {
+    custom
+        .get()
+        .and_then(|bytes| Decoder::new(BufReader::new(Cursor::new(bytes.as_slice()))).ok())
+        .or_else(|| Decoder::new(BufReader::new(Cursor::new(embedded))).ok())
+}
+
+/// Set by `--no-sound`, or once the audio output fails, to skip all sounds for the session.
+static SOUND_DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns sounds off for the rest of the session, logging why the first time.
+fn disable_sound(reason: &str) {
+    if !SOUND_DISABLED.swap(true, Ordering::SeqCst) {
+        eprintln!("Warning: {}. Sounds are off for this session.", reason);
+    }
+}
+
+/// Runs `play` against the default output device unless sounds are off. The sink
+/// panics when no device can be opened; that disables sounds instead of failing the caller.
+fn with_output_device(play: impl FnOnce()) {
+    if SOUND_DISABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    if default_device_sink::default_device_name().is_none() {
+        disable_sound("No audio output device found");
+        return;
+    }
+    if std::panic::catch_unwind(std::panic::AssertUnwindSafe(play)).is_err() {
+        disable_sound("The audio output device could not be opened");
+    }
+}
+
+fn tick_loop(stop_rx: mpsc::Receiver<()>) {
+    with_output_device(|| {
+        let tick_sink = DefaultDeviceSink::new();
+        loop {
+            if stop_rx.try_recv().is_ok() {
+                tick_sink.stop();
+                break;
+            }
+            if tick_sink.empty() {
+                if let Some(decoder) = decode_sound(&TICK_OVERRIDE, TICK_BYTES) {
+                    tick_sink.stop();
+                    tick_sink.append(decoder);
+                } else {
+                    tick_sink.stop();
+                    tick_sink.append(
+                        SineWave::new(880.0)
+                            .take_duration(Duration::from_millis(50))
+                            .amplify(0.20),
+                    );
+                }
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+    });
+}
+
+/// Loops the tick sound in the background until dropped.
+struct Ticks(Option<(mpsc::Sender<()>, thread::JoinHandle<()>)>);
+
+impl Ticks {
+    fn start() -> Self {
+        if SOUND_DISABLED.load(Ordering::Relaxed) {
+            return Ticks(None);
+        }
+        let (tick_tx, tick_rx) = mpsc::channel();
+        let tick_handle = thread::spawn(move || tick_loop(tick_rx));
+        Ticks(Some((tick_tx, tick_handle)))
+    }
+}
+
+impl Drop for Ticks {
+    fn drop(&mut self) {
+        if let Some((tick_tx, tick_handle)) = self.0.take() {
+            let _ = tick_tx.send(());
+            let _ = tick_handle.join();
+        }
+    }
+}
+
+fn play_failure_sound() {
+    with_output_device(|| {
+        let sink = DefaultDeviceSink::new();
+        if let Some(decoder) = decode_sound(&FAILED_OVERRIDE, FAILED_BYTES) {
+            sink.append(decoder);
+        } else {
+            sink.append(
+                SineWave::new(440.0)
+                    .take_duration(Duration::from_millis(150))
+                    .amplify(0.20),
+            );
+        }
+        sink.sleep_until_end();
+    });
+}
+
+// --- Helper Functions (Full Implementations) ---
+/// Opens the clipboard, retrying in longer rounds (with a waiting tone) while another
+/// application holds it, before failing with a message naming the likely holder.
+fn open_clipboard(args: &Args, purpose: &str) -> Result<Clipboard> {
+    let mut last_error = match Clipboard::new_attempts(10) {
+        Ok(clip) => return Ok(clip),
+        Err(e) => e,
+    };
+
+    for round in 1..=args.clipboard_retry_rounds {
+        let holder = window_info::clipboard_holder_name();
+        println!(
+            "Clipboard is busy{} (round {}/{}). Waiting {}ms before retrying to {}...",
+            holder
+                .as_deref()
+                .map(|name| format!(" (held by {})", name))
+                .unwrap_or_default(),
+            round,
+            args.clipboard_retry_rounds,
+            args.clipboard_retry_delay_ms,
+            purpose
+        );
+        play_sound(SoundType::Waiting);
+        thread::sleep(Duration::from_millis(args.clipboard_retry_delay_ms));
+        match Clipboard::new_attempts(10) {
+            Ok(clip) => return Ok(clip),
+            Err(e) => last_error = e,
+        }
+    }
+
+    let culprit = match window_info::clipboard_holder_name() {
+        Some(name) => format!("'{}' appears to be holding it open", name),
+        None => "another application appears to be holding it open".to_string(),
+    };
+    Err(anyhow!(
+        "Failed to open clipboard to {}: {} ({}). Try closing clipboard managers or remote-desktop clipboard sync.",
+        purpose,
+        last_error,
+        culprit
+    ))
+}
+
+fn get_clipboard_content(args: &Args) -> Result<ClipboardContent> {
+    let _clip = open_clipboard(args, "read content")?;
+
+    fn try_get_clipboard_content(
+        prefer_text: bool,
+        combine_when_both: bool,
+    ) -> Result<ClipboardContent, clipboard_win::ErrorCode> {
+        match get_clipboard::<Vec<String>, _>(formats::FileList) {
+            Ok(files) => {
+                println!("Clipboard contains FileList: {:?}", files);
+                if clipboard_win::is_format_avail(formats::CF_BITMAP) {
+                    if !combine_when_both {
+                        println!("Clipboard also contains a Bitmap; ignoring it (use --combine-when-both to process both).");
+                    } else {
+                        match get_clipboard::<Vec<u8>, _>(formats::Bitmap) {
+                            Ok(bitmap) => {
+                                println!(
+                                    "Clipboard also contains Bitmap data ({} bytes). Processing both.",
+                                    bitmap.len()
+                                );
+                                return Ok(ClipboardContent::FileListAndBitmap { files, bitmap });
+                            }
+                            Err(e) => {
+                                println!("Warning: Failed to get Bitmap alongside FileList: {}", e)
+                            }
+                        }
+                    }
+                }
+                return Ok(ClipboardContent::FileList(files));
+            }
+            Err(e) => {
+                if e.raw_code() != CLIPBRD_E_UNSUPPORTEDFORMAT {
+                    println!("Warning: Failed to get FileList: {}. Trying Bitmap.", e);
+                } else {
+                    println!("Clipboard does not contain FileList format. Trying Bitmap.");
+                }
+            }
+        }
+
+        if prefer_text {
+            if let Some(rich) = try_get_rich_text() {
+                return Ok(rich);
+            }
+        }
+
+        match get_clipboard::<Vec<u8>, _>(formats::Bitmap) {
+            Ok(bitmap_data) => {
+                println!(
+                    "Clipboard contains Bitmap data ({} bytes).",
+                    bitmap_data.len()
+                );
+                return Ok(ClipboardContent::Bitmap(bitmap_data));
+            }
+            Err(e) => {
+                if e.raw_code() != CLIPBRD_E_UNSUPPORTEDFORMAT {
+                    println!("Warning: Failed to get Bitmap: {}. Trying Text.", e);
+                } else {
+                    println!("Clipboard does not contain Bitmap format. Trying Text.");
+                }
+            }
+        }
+
+        match get_clipboard::<String, _>(formats::Unicode) {
+            Ok(text) => {
+                println!("Clipboard contains Text ({} chars).", text.chars().count());
+                Ok(ClipboardContent::Text(text))
+            }
+            Err(e) => {
+                if e.raw_code() != CLIPBRD_E_UNSUPPORTEDFORMAT {
+                    println!("Warning: Failed to get Text: {}", e);
+                } else {
+                    println!("Clipboard does not contain Text format either.");
+                }
+                Err(e) // Return specific error
+            }
+        }
+    }
+
+    // _clip drops after reading
+    try_get_clipboard_content(args.prefer_clipboard_text, args.combine_when_both).map_err(|e| {
+        // Map ErrorCode -> anyhow::Error
+        anyhow!(
+            "Failed to get supported content (FileList/Bitmap/Text) from clipboard: {}",
+            e
+        )
+    })
+}
+
+/// Reads CF_HTML or RTF data from the already-open clipboard and extracts its plain text.
+/// Returns `None` when neither format is present or the extracted text is empty.
+fn try_get_rich_text() -> Option<ClipboardContent> {
+    let html_format = formats::Html::new().map(|html| html.code());
+    let rtf_format = clipboard_win::register_format("Rich Text Format").map(|code| code.get());
+
+    for (name, format) in [("HTML", html_format), ("RTF", rtf_format)] {
+        let Some(format) = format.filter(|&code| clipboard_win::is_format_avail(code)) else {
+            continue;
+        };
+        let mut raw = Vec::new();
+        if let Err(e) = clipboard_win::raw::get_vec(format, &mut raw) {
+            println!("Warning: Failed to get {} from clipboard: {}", name, e);
+            continue;
+        }
+        let text = if name == "HTML" {
+            markup::html_to_text(&markup::html_clipboard_fragment(&raw))
+        } else {
+            markup::rtf_to_text(&String::from_utf8_lossy(&raw))
+        };
+        if text.is_empty() {
+            println!("Clipboard {} contains no text. Ignoring it.", name);
+            continue;
+        }
+        println!(
+            "Clipboard contains {} ({} chars of plain text).",
+            name,
+            text.chars().count()
+        );
+        return Some(ClipboardContent::RichText { format, raw, text });
+    }
+    None
+}
+
+fn restore_clipboard(content: ClipboardContent, args: &Args) -> Result<()> {
+    let _clip = open_clipboard(args, "restore original content")?;
+
+    let restored = match content {
+        ClipboardContent::Bitmap(data) => {
+            println!("Restoring Bitmap to clipboard...");
+            formats::Bitmap
+                .write_clipboard(&data)
+                .map_err(|e| anyhow!("Failed to restore Bitmap to clipboard: {}", e))
+            // Map ErrorCode
+        }
+        ClipboardContent::FileList(files) => {
+            println!("Restoring FileList to clipboard...");
+            formats::FileList
+                .write_clipboard(&files)
+                .map_err(|e| anyhow!("Failed to restore FileList to clipboard: {}", e))
+            // Map ErrorCode
+        }
+        ClipboardContent::FileListAndBitmap { files, bitmap } => {
+            println!("Restoring FileList and Bitmap to clipboard...");
+            clipboard_win::raw::set_file_list(&files)
+                .and_then(|()| {
+                    clipboard_win::raw::set_bitmap_with(&bitmap, clipboard_win::options::NoClear)
+                })
+                .map_err(|e| anyhow!("Failed to restore FileList and Bitmap to clipboard: {}", e))
+        }
+        ClipboardContent::Text(text) => {
+            println!("Restoring Text to clipboard...");
+            formats::Unicode
+                .write_clipboard(&text)
+                .map_err(|e| anyhow!("Failed to restore Text to clipboard: {}", e))
+        }
+        ClipboardContent::RichText { format, raw, text } => {
+            println!("Restoring rich text to clipboard...");
+            clipboard_win::raw::set(format, &raw)
+                .and_then(|()| {
+                    clipboard_win::raw::set_string_with(&text, clipboard_win::options::NoClear)
+                })
+                .map_err(|e| anyhow!("Failed to restore rich text to clipboard: {}", e))
+        }
+    };
+    if restored.is_ok() {
+        note_own_clipboard_write();
+    }
+    restored
+    // _clip drops here
+}
+
+/// The clipboard's change counter (`GetClipboardSequenceNumber`), if available.
+fn clipboard_sequence_number() -> Option<u32> {
+    clipboard_win::seq_num().map(|seq| seq.get())
+}
+
+/// Sequence number right after our latest clipboard write, so `--watch-clipboard`
+/// doesn't treat restored images or pasted text as new copies. 0 means none yet.
+static OWN_CLIPBOARD_SEQ: AtomicU32 = AtomicU32::new(0);
+
+fn note_own_clipboard_write() {
+    if let Some(seq) = clipboard_sequence_number() {
+        OWN_CLIPBOARD_SEQ.store(seq, Ordering::SeqCst);
+    }
+}
+
+/// Restores `content` unless the clipboard changed since `expected_seq` was taken, in
+/// which case the user or another app owns it now. `--force-restore` always restores.
+fn restore_clipboard_unless_changed(
+    content: ClipboardContent,
+    expected_seq: Option<u32>,
+    args: &Args,
+) -> Result<()> {
+    if !args.force_restore {
+        if let (Some(expected), Some(current)) = (expected_seq, clipboard_sequence_number()) {
+            if current != expected {
+                println!(
+                    "Clipboard changed since it was read (sequence {} -> {}). Skipping restore.",
+                    expected, current
+                );
+                return Ok(());
+            }
+        }
+    }
+    restore_clipboard(content, args)?;
+    println!("Original clipboard content restored.");
+    Ok(())
+}
+
+/// `--verify-paste`: the target's contents can't be read back, so this checks what can
+/// go wrong around it. Another clipboard write or a focus change between setting the
+/// text and now means the paste may have delivered something else, or nothing.
+fn verify_paste(
+    expected: &str,
+    pasted_seq: Option<u32>,
+    target_window: winapi::shared::windef::HWND,
+    args: &Args,
+) {
+    let mut verified = true;
+    if let (Some(pasted), Some(current)) = (pasted_seq, clipboard_sequence_number()) {
+        if current != pasted {
+            println!(
+                "Warning: The clipboard changed during the paste (sequence {} -> {}). The paste may not have landed.",
+                pasted, current
+            );
+            verified = false;
+        }
+    }
+    if verified {
+        let readback = open_clipboard(args, "verify paste").and_then(|_clip| {
+            get_clipboard::<String, _>(formats::Unicode)
+                .map_err(|e| anyhow!("Failed to read clipboard text: {}", e))
+        });
+        match readback {
+            Ok(text) if text == expected => {}
+            Ok(_) => {
+                println!("Warning: The clipboard no longer holds the pasted text. The paste may not have landed.");
+                verified = false;
+            }
+            Err(e) => {
+                println!(
+                    "Warning: Could not read the clipboard back to verify the paste: {:?}",
+                    e
+                );
+                verified = false;
+            }
+        }
+    }
+    if window_info::foreground_handle() != target_window {
+        println!("Warning: Focus left the target window during the paste. The text may have gone elsewhere.");
+        verified = false;
+    }
+    if verified {
+        println!("Paste verified: clipboard and focus unchanged.");
+    }
+}
+
+fn set_clipboard_string_helper(text: &str, args: &Args) -> Result<()> {
+    let _clip = open_clipboard(args, "set string")?;
+
+    formats::Unicode
+        .write_clipboard(&text)
+        .map_err(|e| anyhow!("Failed to set clipboard string: {}", e))?; // Map ErrorCode
+    if args.extra_clipboard_formats {
+        clipboard_win::raw::set_without_clear(formats::CF_TEXT, &to_ansi_bytes(text))
+            .map_err(|e| anyhow!("Failed to set ANSI clipboard text: {}", e))?;
+        if let Some(html) = formats::Html::new() {
+            clipboard_win::raw::set_html(html.code(), &markup::text_to_html(text))
+                .map_err(|e| anyhow!("Failed to set HTML clipboard text: {}", e))?;
+        }
+    }
+    note_own_clipboard_write();
+    Ok(())
+    // _clip drops here
+}
+
+/// Encodes text in the system ANSI code page as NUL-terminated `CF_TEXT` data.
+fn to_ansi_bytes(text: &str) -> Vec<u8> {
+    use winapi::um::{stringapiset::WideCharToMultiByte, winnls::CP_ACP};
+
+    let wide: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+    unsafe {
+        let len = WideCharToMultiByte(
+            CP_ACP,
+            0,
+            wide.as_ptr(),
+            wide.len() as i32,
+            std::ptr::null_mut(),
+            0,
+            std::ptr::null(),
+            std::ptr::null_mut(),
+        );
+        if len <= 0 {
+            return vec![0];
+        }
+        let mut bytes = vec![0u8; len as usize];
+        WideCharToMultiByte(
+            CP_ACP,
+            0,
+            wide.as_ptr(),
+            wide.len() as i32,
+            bytes.as_mut_ptr() as *mut i8,
+            len,
+            std::ptr::null(),
+            std::ptr::null_mut(),
+        );
+        bytes
+    }
+}
+
+// --- OCR Helpers ---
+/// Drives the tick sound and the `--annotate-output`/`--boxes-json` exports from
+/// pipeline events.
+struct TriggerProgress<'a> {
+    args: &'a Args,
+    ticks: Option<Ticks>,
+}
+
+impl<'a> TriggerProgress<'a> {
+    fn new(args: &'a Args) -> Self {
+        TriggerProgress { args, ticks: None }
+    }
+}
+
+impl pipeline::ProgressCallback for TriggerProgress<'_> {
+    fn on_start(&mut self, kind: pipeline::ContentKind) {
+        // Transcription always ticks; OCR is usually quick, so only with --beeps.
+        let ticks = match kind {
+            pipeline::ContentKind::Ocr => self.args.beeps,
+            pipeline::ContentKind::Transcription => true,
+        };
+        if ticks {
+            self.ticks = Some(Ticks::start());
+        }
+    }
+
+    fn on_image_prepared(&mut self, image_path: &Path) {
+        let args = self.args;
+        if args.annotate_output.is_some() || args.boxes_json.is_some() {
+            if let Err(e) = export_word_boxes(image_path, args) {
+                eprintln!("Warning: Failed to export word boxes: {:?}", e);
+            }
+        }
+        if let Err(e) = export_structured_ocr(image_path, args) {
+            eprintln!("Warning: Failed to export OCR output: {:?}", e);
+        }
+    }
+
+    fn on_done(&mut self, _result: &Result<pipeline::ProcessOutcome>) {
+        self.ticks = None;
+    }
+}
+
+/// Runs Tesseract in TSV mode on the prepared image and writes the word boxes
+/// to `--annotate-output` (drawn on a copy of the image) and/or `--boxes-json`.
+fn export_word_boxes(image_path: &Path, args: &Args) -> Result<()> {
+    let settings = args.pipeline_config().ocr;
+    let mut command = ocr::tesseract_command(image_path, &settings, settings.psm);
+    command.arg("tsv");
+    let tsv = ocr::run_tesseract_command(command, &settings)?;
+    let words = annotate::parse_tsv_words(&tsv);
+    if let Some(output_path) = &args.annotate_output {
+        annotate::save_annotated(image_path, &words, output_path)?;
+        println!(
+            "Saved annotated image with {} word boxes to {:?}",
+            words.len(),
+            output_path
+        );
+    }
+    if let Some(output_path) = &args.boxes_json {
+        annotate::save_json(&words, output_path)?;
+        println!(
+            "Saved {} word boxes as JSON to {:?}",
+            words.len(),
+            output_path
+        );
+    }
+    Ok(())
+}
+
+/// Writes Tesseract's `--ocr-output-format` output for one image to `--output-file`,
+/// replacing the previous contents.
+fn export_structured_ocr(image_path: &Path, args: &Args) -> Result<()> {
+    let (Some(config), Some(output_path)) =
+        (args.ocr_output_format.tesseract_config(), &args.output_file)
+    else {
+        return Ok(());
+    };
+    let settings = args.pipeline_config().ocr;
+    let mut command = ocr::tesseract_command(image_path, &settings, settings.psm);
+    command.arg(config);
+    let output = ocr::run_tesseract_command(command, &settings)?;
+    std::fs::write(output_path, output)
+        .with_context(|| format!("Failed to write {} output to {:?}", config, output_path))?;
+    println!("Saved Tesseract {} output to {:?}", config, output_path);
+    Ok(())
+}
+
+/// OCR of an image or image file through the pipeline.
+fn ocr_content(content: pipeline::Content, args: &Args) -> Result<String> {
+    let mut config = args.pipeline_config();
+    let exports = args.annotate_output.is_some()
+        || args.boxes_json.is_some()
+        || args.ocr_output_format != OcrOutputFormat::Text;
+    if exports {
+        config.cache = None; // A cache hit would skip the Tesseract run the exports hook into.
+    }
+    pipeline::process_content_with_progress(content, &config, &mut TriggerProgress::new(args))
+        .map(|outcome| outcome.text)
+}
+
+/// OCR of an image file, e.g. one copied from Explorer.
+fn ocr_image_file(path: &Path, args: &Args) -> Result<String> {
+    println!("Processing image file with Tesseract OCR...");
+    ocr_content(pipeline::Content::ImageFile(path.to_path_buf()), args)
+}
+
+/// OCRs each image in order, joining them with `--page-separator`.
+fn ocr_images(images: &[&[u8]], args: &Args) -> Result<String> {
+    let mut pages = Vec::with_capacity(images.len());
+    for (index, image_data) in images.iter().enumerate() {
+        if images.len() > 1 {
+            println!("OCR image {}/{}...", index + 1, images.len());
+        }
+        pages.push(ocr_content(
+            pipeline::Content::Image(image_data.to_vec()),
+            args,
+        )?);
+    }
+    Ok(pages.join(&args.pipeline.page_separator))
+}
+
+// --- Transcription Helpers ---
+fn transcribe_audio_file(
+    audio_path: &Path,
+    source: TranscriptSource,
+    args: &Args,
+) -> Result<String> {
+    if args.pipeline.openai_api_key.is_some() || args.pipeline.diarize_backend.is_some() {
+        check_audio_duration(audio_path, args)?;
+    }
+    let mut config = args.pipeline_config();
+    config.transcribe.format = args.transcript_format(source);
+    let outcome = pipeline::process_content_with_progress(
+        pipeline::Content::AudioFile(audio_path.to_path_buf()),
+        &config,
+        &mut TriggerProgress::new(args),
+    )
+    .inspect_err(|_| {
+        play_failure_sound();
+    })?;
+    // The diarization backend's pricing is unknown, so only OpenAI requests are costed.
+    if !outcome.cached && args.pipeline.diarize_backend.is_none() {
+        log_transcription_cost(audio_path, args);
+    }
+    Ok(outcome.text)
+}
+
+/// Copied files and URLs go through the library's input handling, with this binary's
+/// sounds, prompts and exports around each recognition.
+impl input::Recognizer for Args {
+    fn can_transcribe(&self) -> bool {
+        self.pipeline.openai_api_key.is_some() || self.pipeline.diarize_backend.is_some()
+    }
+
+    fn ocr_image_file(&self, path: &Path) -> Result<String> {
+        ocr_image_file(path, self)
+    }
+
+    fn transcribe_audio_file(&self, path: &Path, source: TranscriptSource) -> Result<String> {
+        transcribe_audio_file(path, source, self)
+    }
+}
+
+/// Seconds of audio actually sent, after `--transcribe-duration-secs` truncation.
+fn billed_duration_secs(duration_secs: f64, args: &Args) -> f64 {
+    match args.pipeline.transcribe_duration_secs {
+        Some(max_secs) => duration_secs.min(max_secs as f64),
+        None => duration_secs,
+    }
+}
+
+/// Refuses audio longer than `--max-audio-duration-secs`, or asks first with
+/// `--confirm-long-audio`. A duration that can't be probed is let through.
+fn check_audio_duration(audio_path: &Path, args: &Args) -> Result<()> {
+    if args.max_audio_duration_secs == 0 {
+        return Ok(());
+    }
+    let duration_secs = match transcribe::trans::probe_duration_secs(audio_path) {
+        Ok(secs) => billed_duration_secs(secs, args),
+        Err(e) => {
+            println!("Warning: Couldn't check the audio length: {}", e);
+            return Ok(());
+        }
+    };
+    if duration_secs <= args.max_audio_duration_secs as f64 {
+        return Ok(());
+    }
+
+    let message = format!(
+        "This audio is {:.0} minutes long, over the {:.0} minute limit (--max-audio-duration-secs). Estimated cost: ${:.2}.",
+        duration_secs / 60.0,
+        args.max_audio_duration_secs as f64 / 60.0,
+        duration_secs / 60.0 * args.cost_per_minute
+    );
+    if args.confirm_long_audio
+        && confirm_dialog(
+            "OCRP: Long audio",
+            &format!("{}\n\nTranscribe anyway?", message),
+        )
+    {
+        println!("Long audio confirmed by the user.");
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{} Not transcribing; raise the limit, use --transcribe-duration-secs to send only the start, or pass --confirm-long-audio to be asked.",
+        message
+    ))
+}
+
+/// Shows a topmost Yes/No message box and returns whether Yes was chosen.
+fn confirm_dialog(title: &str, message: &str) -> bool {
+    use winapi::um::winuser::{MessageBoxW, IDYES, MB_ICONWARNING, MB_TOPMOST, MB_YESNO};
+
+    let wide = |text: &str| -> Vec<u16> { text.encode_utf16().chain(Some(0)).collect() };
+    let (title, message) = (wide(title), wide(message));
+    let choice = unsafe {
+        MessageBoxW(
+            std::ptr::null_mut(),
+            message.as_ptr(),
+            title.as_ptr(),
+            MB_YESNO | MB_ICONWARNING | MB_TOPMOST,
+        )
+    };
+    choice == IDYES
+}
+
+/// Running total of estimated transcription spend for this session, in dollars.
+static SESSION_TRANSCRIPTION_COST: Mutex<f64> = Mutex::new(0.0);
+
+/// Logs a rough cost estimate from the audio duration. Purely informational, so any
+/// failure to determine the duration just skips the estimate.
+fn log_transcription_cost(audio_path: &Path, args: &Args) {
+    let duration_secs = match transcribe::trans::probe_duration_secs(audio_path) {
+        Ok(secs) => secs,
+        Err(e) => {
+            println!("Skipping cost estimate: {}", e);
+            return;
+        }
+    };
+    let minutes = billed_duration_secs(duration_secs, args) / 60.0;
+    let cost = minutes * args.cost_per_minute;
+
+    let mut total = SESSION_TRANSCRIPTION_COST
+        .lock()
+        .expect("cost total lock poisoned");
+    *total += cost;
+    println!(
+        "Estimated transcription cost: ${:.4} ({:.2} min @ ${}/min). Session total: ${:.4}",
+        cost, minutes, args.cost_per_minute, *total
+    );
+}
+
+// --- Paste Method Resolution ---
+/// Picks the paste method from the first config rule matching the foreground window,
+/// falling back to `--paste-method`.
+fn resolve_paste_method(args: &Args) -> PasteMethod {
+    if args.file_config.paste_targets.is_empty() {
+        return args.paste_method;
+    }
+    let Some(window) = window_info::foreground_window() else {
+        return args.paste_method;
+    };
+    let rule = args.file_config.paste_targets.iter().find(|rule| {
+        config::pattern_matches(&rule.pattern, window.exe_name.as_deref(), &window.title)
+    });
+    match rule {
+        Some(rule) => {
+            println!(
+                "Paste target rule '{}' matched {} (\"{}\"): using {:?}.",
+                rule.pattern,
+                window.exe_name.as_deref().unwrap_or("<unknown exe>"),
+                window.title,
+                rule.method
+            );
+            rule.method
+        }
+        None => args.paste_method,
+    }
+}
+
+/// Switches `--lang` to the first `[[lang_targets]]` rule matching the foreground
+/// window, keeping the global language when none matches.
+fn apply_app_language(args: &mut Args) {
+    if args.file_config.lang_targets.is_empty() {
+        return;
+    }
+    let window = window_info::foreground_window();
+    let rule = window.as_ref().and_then(|window| {
+        args.file_config.lang_targets.iter().find(|rule| {
+            config::pattern_matches(&rule.pattern, window.exe_name.as_deref(), &window.title)
+        })
+    });
+    match rule {
+        Some(rule) => {
+            println!(
+                "Language rule '{}' matched the foreground window: OCR language {}.",
+                rule.pattern, rule.lang
+            );
+            args.pipeline.lang = rule.lang.clone();
+        }
+        None => println!(
+            "No language rule matched: OCR language {}.",
+            args.pipeline.lang
+        ),
+    }
+}
+
+/// Returns the `--ignore-apps` pattern matching the foreground window, if any.
+fn ignored_app_match(args: &Args) -> Option<&str> {
+    if args.ignore_apps.is_empty() {
+        return None;
+    }
+    let window = window_info::foreground_window()?;
+    args.ignore_apps
+        .iter()
+        .find(|pattern| config::pattern_matches(pattern, window.exe_name.as_deref(), &window.title))
+        .map(String::as_str)
+}
+
+// --- process_clipboard_and_paste (Full Implementation) ---
+/// Reads the clipboard and processes it, re-reading up to `--retry-on-empty` times
+/// while the result is empty in case the triggering copy hadn't completed yet.
+fn read_process_and_paste(args: &Args) -> Result<()> {
+    if args.pre_read_delay_ms > 0 {
+        thread::sleep(Duration::from_millis(args.pre_read_delay_ms));
+    }
+    for attempt in 0..=args.retry_on_empty {
+        if attempt > 0 {
+            println!(
+                "Empty result; re-reading clipboard in {}ms (retry {}/{})...",
+                RETRY_ON_EMPTY_DELAY_MS, attempt, args.retry_on_empty
+            );
+            thread::sleep(Duration::from_millis(RETRY_ON_EMPTY_DELAY_MS));
+        }
+        // Taken before reading, so a copy that races the read counts as a change.
+        let read_seq = clipboard_sequence_number();
+        let original_content = get_clipboard_content(args)
+            .inspect_err(|e| eprintln!("ERROR getting clipboard content: {:?}", e))?;
+        if process_clipboard_and_paste(original_content, read_seq, args)? {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Handles copied files and folders with `--unknown-file-policy`, `--dir-policy` and
+/// `--on-missing-key`.
+fn process_file_list(files: &[String], args: &Args) -> Result<String> {
+    input::process_files(files, &args.pipeline.input_settings(), args)
+}
+
+/// Transcribes a finished microphone recording and pastes the text, restoring
+/// whatever was on the clipboard beforehand.
+fn transcribe_recording_and_paste(recording: record::Recording, args: &Args) -> Result<()> {
+    let started = Instant::now();
+    let wav_file = recording.finish()?;
+    println!("Recording saved to {:?}.", wav_file.path());
+    if let Some(outcome) = input::missing_key_outcome(&args.pipeline.input_settings(), args) {
+        return outcome.map(|_| ());
+    }
+    let transcription = transcribe_audio_file(wav_file.path(), TranscriptSource::Microphone, args);
+    if let Ok(text) = &transcription {
+        post_webhook(args, "microphone", "recording", text);
+    }
+    let result_chars = result_chars(&transcription);
+    let read_seq = clipboard_sequence_number();
+    let original_content = get_clipboard_content(args).unwrap_or_else(|e| {
+        println!(
+            "Nothing to restore on the clipboard ({}). It will hold an empty string afterwards.",
+            e
+        );
+        ClipboardContent::Text(String::new())
+    });
+    let delivered = deliver_processed_text(transcription, original_content, read_seq, args);
+    log_run(
+        args,
+        "microphone",
+        "recording",
+        started,
+        result_chars,
+        delivered.as_ref().err(),
+    );
+    delivered.map(|_| ())
+}
+
+/// Processes the clipboard content and pastes the result. Returns `false` when the
+/// recognized text was empty and nothing was pasted.
+fn process_clipboard_and_paste(
+    original_content: ClipboardContent,
+    read_seq: Option<u32>,
+    args: &Args,
+) -> Result<bool> {
+    let started = Instant::now();
+    let kind = original_content.kind();
+    let processed_text_result = recognize_clipboard_content(&original_content, args);
+
+    if let Some(dir) = &args.archive_bitmaps {
+        for image_data in original_content.images() {
+            if let Err(e) = image_ops::check_pixel_count(image_data, args.pipeline.max_pixels) {
+                eprintln!("Warning: Not archiving the clipboard bitmap: {:?}", e);
+                continue;
+            }
+            match archive::archive_bitmap(dir, image_data, "clipboard", &processed_text_result) {
+                Ok(stem) => println!("Archived clipboard bitmap as {:?}.", dir.join(stem)),
+                Err(e) => eprintln!("Warning: Failed to archive clipboard bitmap: {:?}", e),
+            }
+        }
+    }
+
+    if let Ok(text) = &processed_text_result {
+        post_webhook(args, "hotkey", kind, text);
+    }
+    let result_chars = result_chars(&processed_text_result);
+    let delivered = deliver_processed_text(processed_text_result, original_content, read_seq, args);
+    log_run(
+        args,
+        "hotkey",
+        kind,
+        started,
+        result_chars,
+        delivered.as_ref().err(),
+    );
+    delivered
+}
+
+/// Turns clipboard content into text: OCR, transcription or extracted rich text.
+/// Leaves the clipboard itself alone.
+fn recognize_clipboard_content(content: &ClipboardContent, args: &Args) -> Result<String> {
+    match content {
+        ClipboardContent::FileList(files) => process_file_list(files, args),
+        ClipboardContent::FileListAndBitmap { files, .. } => process_file_list(files, args)
+            .and_then(|files_text| {
+                println!("Processing clipboard image with Tesseract OCR...");
+                let image_text = ocr_images(&content.images(), args)?;
+                Ok(format!(
+                    "[Files]\n{}\n\n[Image]\n{}",
+                    files_text.trim(),
+                    image_text.trim()
+                ))
+            }),
+        ClipboardContent::Text(text) => match input::as_single_url(text) {
+            Some(url) => {
+                println!("Detected URL in clipboard text: {}", url);
+                input::transcribe_url(url, &args.pipeline.input_settings(), args)
+            }
+            None if args.text_passthrough => {
+                println!("Clipboard holds plain text. Pasting it as is (--text-passthrough).");
+                Ok(text.clone())
+            }
+            None => Err(anyhow!(
+                "Clipboard contains text, but it is not a single http(s) URL. Nothing to process."
+            )),
+        },
+        ClipboardContent::RichText { text, .. } => {
+            println!("Using plain text extracted from the clipboard instead of OCR.");
+            Ok(text.clone())
+        }
+        ClipboardContent::Bitmap(_) => {
+            println!("Processing clipboard image with Tesseract OCR...");
+            ocr_images(&content.images(), args)
+        }
+    }
+}
+
+/// The last text pasted this session, replayed by `--repeat-key`. Never written to disk.
+static LAST_RESULT: Mutex<Option<String>> = Mutex::new(None);
+
+/// Pastes the last successful result into the current window, skipping recognition.
+fn repaste_last_result(args: &Args) -> Result<()> {
+    let last_text = LAST_RESULT
+        .lock()
+        .expect("last result lock poisoned")
+        .clone()
+        .ok_or_else(|| anyhow!("Nothing to repeat yet: no text has been pasted this session."))?;
+    println!(
+        "Re-pasting the last result ({} chars)...",
+        last_text.chars().count()
+    );
+    let started = Instant::now();
+    let result_chars = last_text.trim().chars().count();
+    let read_seq = clipboard_sequence_number();
+    let original_content = get_clipboard_content(args)
+        .context("Failed to read the clipboard to restore after re-pasting")?;
+    let delivered = deliver_processed_text(Ok(last_text), original_content, read_seq, args);
+    log_run(
+        args,
+        "repeat",
+        "last-result",
+        started,
+        result_chars,
+        delivered.as_ref().err(),
+    );
+    delivered.map(|_| ())
+}
+
+/// Pastes the processed text, restoring the original clipboard afterwards, on an
+/// empty result or on error. Returns `false` when the text was empty and nothing was pasted.
+/// `read_seq` is the clipboard sequence number from when `original_content` was read.
+fn deliver_processed_text(
+    processed_text_result: Result<String>,
+    original_content: ClipboardContent,
+    read_seq: Option<u32>,
+    args: &Args,
+) -> Result<bool> {
+    match processed_text_result {
+        Ok(processed_text) => {
+            // Whitespace-only text counts as empty whatever --trim-mode keeps.
+            if processed_text.trim().is_empty() {
+                println!("Processing resulted in empty text. Skipping paste.");
+                restore_clipboard_unless_changed(original_content, read_seq, args).with_context(
+                    || "Failed to restore original clipboard content after empty result",
+                )?;
+                // Still consider this a "success" in terms of overall operation completion,
+                // so Success beep might still be appropriate if enabled.
+                Ok(false)
+            } else {
+                let output_text = finalize_text(&processed_text, args);
+                println!("Processed Text (first 100 chars): {:.100}...", output_text);
+                append_output_file(&output_text, args);
+                if args.no_paste {
+                    println!("--no-paste is set. Printing instead of pasting:");
+                    print_text(&output_text, args);
+                    *LAST_RESULT.lock().expect("last result lock poisoned") = Some(processed_text);
+                    return Ok(true);
+                }
+
+                if args.password_field_policy != PasswordFieldPolicy::Allow
+                    && window_info::focused_is_password() == Some(true)
+                {
+                    if args.password_field_policy == PasswordFieldPolicy::Skip {
+                        set_clipboard_string_helper(&output_text, args)
+                            .with_context(|| "Failed to place processed text onto clipboard")?;
+                        println!("Warning: The focused field is a password field. Not pasting; the text is on the clipboard (--password-field-policy skip).");
+                        *LAST_RESULT.lock().expect("last result lock poisoned") =
+                            Some(processed_text);
+                        return Ok(true);
+                    }
+                    println!("Warning: Pasting into a password field. Password managers may keep this text in their history.");
+                }
+
+                // Before any clipboard writes, which can make focus flicker on some systems.
+                let target_window = window_info::foreground_handle();
+                let paste_method = resolve_paste_method(args);
+                if args.newline_before_paste {
+                    println!("Pressing Enter before pasting...");
+                    paste::press_enter(args.simulate_retries)
+                        .map_err(|e| anyhow!("Simulate Enter error: {}", e))?;
+                }
+                match paste_method {
+                    PasteMethod::Clipboard => {
+                        set_clipboard_string_helper(&output_text, args)
+                            .with_context(|| "Failed to place processed text onto clipboard")?;
+                        let pasted_seq = clipboard_sequence_number();
+                        println!(
+                            "Processed text placed on clipboard. Simulating paste (Ctrl+V)..."
+                        );
+                        thread::sleep(Duration::from_millis(args.paste_delay_ms));
+                        if args.reassert_focus && !window_info::reassert_foreground(target_window) {
+                            println!("Warning: Could not return focus to the target window before pasting.");
+                        }
+                        paste::send_ctrl_v(args.paste_sequence_variant, args.simulate_retries)
+                            .map_err(|e| anyhow!("Simulate Ctrl+V error: {}", e))?;
+                        play_success_after_paste(args);
+
+                        thread::sleep(Duration::from_millis(args.paste_delay_ms));
+                        if args.verify_paste {
+                            verify_paste(&output_text, pasted_seq, target_window, args);
+                        }
+                        restore_clipboard_unless_changed(original_content, pasted_seq, args)
+                            .with_context(|| "Failed to restore original content to clipboard")?;
+                    }
+                    PasteMethod::Type | PasteMethod::Bracketed => {
+                        println!("Typing processed text (clipboard left untouched)...");
+                        let delay = Duration::from_millis(args.type_delay_ms);
+                        let typed = if paste_method == PasteMethod::Bracketed {
+                            paste::type_bracketed(&output_text, delay)
+                        } else {
+                            paste::type_text(&output_text, delay)
+                        };
+                        typed.map_err(|e| anyhow!("Simulate typing error: {}", e))?;
+                        play_success_after_paste(args);
+                    }
+                }
+                *LAST_RESULT.lock().expect("last result lock poisoned") = Some(processed_text);
+                Ok(true)
+            }
+        }
+        Err(e) => {
+            eprintln!("ERROR processing clipboard content: {:?}", e);
+            if let Err(restore_err) =
+                restore_clipboard_unless_changed(original_content, read_seq, args)
+            {
+                eprintln!(
+                    "Additionally failed to restore clipboard: {:?}",
+                    restore_err
+                );
+            }
+            Err(e) // Propagate the error
+        }
+    }
+}
+
+/// Applies `--trim-mode`, `--ascii-normalize`, the `--replace` rules, `--case` and
+/// `--escape-newlines` to recognized text.
+fn finalize_text(text: &str, args: &Args) -> String {
+    let trimmed_text = text_transform::apply_trim(text, args.trim_mode);
+    let normalized_text = if args.ascii_normalize {
+        Cow::Owned(text_transform::ascii_normalize(trimmed_text))
+    } else {
+        Cow::Borrowed(trimmed_text)
+    };
+    let replaced_text = text_transform::apply_replacements(&normalized_text, &args.replace_rules);
+    let cased_text = text_transform::apply_case(&replaced_text, args.case);
+    if args.escape_newlines {
+        text_transform::escape_newlines(&cased_text)
+    } else {
+        cased_text
+    }
+}
+
+/// Appends delivered text to `--output-file`, if set and `--ocr-output-format` is
+/// `text`. A failed write is only logged.
+fn append_output_file(text: &str, args: &Args) {
+    if args.ocr_output_format != OcrOutputFormat::Text {
+        return;
+    }
+    if let Some(path) = &args.output_file {
+        if let Err(e) = output_file::append(path, text, args.output_encoding) {
+            eprintln!("Warning: {:?}", e);
+        }
+    }
+}
+
+/// Length of a recognition result as logged to `--log-file`; 0 for errors.
+fn result_chars(result: &Result<String>) -> usize {
+    result
+        .as_ref()
+        .map_or(0, |text| text.trim().chars().count())
+}
+
+/// Appends a run to `--log-file`, if set. A failed write is only logged.
+fn log_run(
+    args: &Args,
+    source: &str,
+    kind: &str,
+    started: Instant,
+    result_chars: usize,
+    error: Option<&anyhow::Error>,
+) {
+    if let Some(path) = &args.log_file {
+        let record = run_log::RunRecord::new(source, kind, result_chars, started.elapsed(), error);
+        if let Err(e) = run_log::append(path, args.log_max_bytes, &record) {
+            eprintln!("Warning: {:?}", e);
+        }
+    }
+}
+
+/// Sends the finished text to `--webhook-url`, if set, without waiting for the response.
+fn post_webhook(args: &Args, source: &str, kind: &str, text: &str) {
+    let Some(url) = &args.webhook_url else {
+        return;
+    };
+    let output_text = finalize_text(text, args);
+    if !output_text.trim().is_empty() {
+        webhook::post(
+            url,
+            Duration::from_secs(args.webhook_timeout),
+            &output_text,
+            kind,
+            source,
+        );
+    }
+}
+
+/// Prints one-shot output to stdout, also placing it on the clipboard with `--copy`.
+fn emit_one_shot_text(text: &str, args: &Args) -> Result<()> {
+    let output_text = finalize_text(text, args);
+    append_output_file(&output_text, args);
+    if args.copy {
+        set_clipboard_string_helper(&output_text, args)
+            .with_context(|| "Failed to copy the text to the clipboard")?;
+        eprintln!(
+            "Copied {} chars to the clipboard.",
+            output_text.chars().count()
+        );
+    }
+    print_text(&output_text, args);
+    Ok(())
+}
+
+/// Prints text to stdout, highlighted with `--highlight` when stdout is a terminal.
+fn print_text(text: &str, args: &Args) {
+    use std::io::IsTerminal;
+
+    if args.highlight && std::io::stdout().is_terminal() && highlight::enable_ansi_output() {
+        if let Some(extension) = highlight::guess_extension(text) {
+            eprintln!("(Highlighted as .{})", extension);
+            println!("{}", highlight::highlight(text, extension));
+            return;
         }
-        thread::sleep(Duration::from_millis(100));
     }
+    println!("{}", text);
 }
 
-fn play_failure_sound() {
-    let sink = DefaultDeviceSink::new();
-    if let Ok(decoder) = Decoder::new(BufReader::new(Cursor::new(FAILED_BYTES))) {
-        sink.append(decoder);
-    } else {
-        sink.append(
-            SineWave::new(440.0)
-                .take_duration(Duration::from_millis(150))
-                .amplify(0.20),
-        );
+/// `--success-sound-timing after-paste`: the success sound, right after the keystrokes.
+fn play_success_after_paste(args: &Args) {
+    if args.beeps && args.success_sound_timing == SuccessSoundTiming::AfterPaste {
+        play_sound(SoundType::Success);
     }
-    sink.sleep_until_end();
 }
 
-// --- Helper Functions (Full Implementations) ---
-fn get_clipboard_content() -> Result<ClipboardContent> {
-    fn try_get_clipboard_content() -> Result<ClipboardContent, clipboard_win::ErrorCode> {
-        let _clip = Clipboard::new_attempts(10)?; // Open clipboard
-
-        match get_clipboard::<Vec<String>, _>(formats::FileList) {
-            Ok(files) => {
-                println!("Clipboard contains FileList: {:?}", files);
-                return Ok(ClipboardContent::FileList(files));
-            }
-            Err(e) => {
-                if e.raw_code() != CLIPBRD_E_UNSUPPORTEDFORMAT {
-                    println!("Warning: Failed to get FileList: {}. Trying Bitmap.", e);
-                } else {
-                    println!("Clipboard does not contain FileList format. Trying Bitmap.");
-                }
+/// Plays the success/error sound for a finished trigger and logs any error.
+fn report_trigger_result(result: Result<()>, args: &Args) {
+    match result {
+        Ok(()) => {
+            // Play SUCCESS sound only if flag is set
+            if args.beeps && args.success_sound_timing == SuccessSoundTiming::AfterRestore {
+                play_sound(SoundType::Success);
             }
         }
-
-        match get_clipboard::<Vec<u8>, _>(formats::Bitmap) {
-            Ok(bitmap_data) => {
-                println!(
-                    "Clipboard contains Bitmap data ({} bytes).",
-                    bitmap_data.len()
-                );
-                return Ok(ClipboardContent::Bitmap(bitmap_data));
-            }
-            Err(e) => {
-                if e.raw_code() != CLIPBRD_E_UNSUPPORTEDFORMAT {
-                    println!("Warning: Failed to get Bitmap: {}", e);
-                } else {
-                    println!("Clipboard does not contain Bitmap format either.");
-                }
-                return Err(e); // Return specific error
-            }
+        Err(e) => {
+            // Always play ERROR sound
+            play_sound(SoundType::Error);
+            // Print error for visibility
+            eprintln!("{}", e);
         }
-        // _clip drops here
     }
-
-    try_get_clipboard_content().map_err(|e| {
-        // Map ErrorCode -> anyhow::Error
-        anyhow!(
-            "Failed to get supported content (FileList/Bitmap) from clipboard: {}",
-            e
-        )
-    })
+    println!("--- Worker ready for next trigger ---");
 }
 
-fn restore_clipboard(content: ClipboardContent) -> Result<()> {
-    let _clip = Clipboard::new_attempts(10)
-        .map_err(|e| anyhow!("Failed to open clipboard for restoration: {}", e))?; // Map ErrorCode
+// --- API Key Check ---
+/// Lists models to confirm the key authenticates. Costs nothing and uploads nothing.
+fn check_openai_key(api_key: &str) -> Result<()> {
+    let rt = Runtime::new().context("Failed to create Tokio runtime for key check")?;
+    let client = Client::with_config(OpenAIConfig::new().with_api_key(api_key));
+    rt.block_on(client.models().list())
+        .map(|_| ())
+        .map_err(|e| anyhow!("OpenAI API key check failed: {}", e))
+}
 
-    match content {
-        ClipboardContent::Bitmap(data) => {
-            println!("Restoring Bitmap to clipboard...");
-            formats::Bitmap
-                .write_clipboard(&data)
-                .map_err(|e| anyhow!("Failed to restore Bitmap to clipboard: {}", e))
-            // Map ErrorCode
-        }
-        ClipboardContent::FileList(files) => {
-            println!("Restoring FileList to clipboard...");
-            formats::FileList
-                .write_clipboard(&files)
-                .map_err(|e| anyhow!("Failed to restore FileList to clipboard: {}", e))
-            // Map ErrorCode
+// --- Config Hot Reload ---
+/// Watches the config file and swaps freshly merged settings into `shared_args` on change.
+/// `cli_args` are the command-line settings the file is layered on top of.
+fn spawn_config_watcher(path: PathBuf, cli_args: Args, shared_args: Arc<RwLock<Args>>) {
+    thread::spawn(move || {
+        let (fs_tx, fs_rx) = mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(fs_tx) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("Warning: Config hot reload disabled (watcher error): {}", e);
+                return;
+            }
+        };
+        // Watch the directory: editors often replace the file rather than writing in place.
+        let watch_dir = path.parent().unwrap_or(Path::new(".")).to_path_buf();
+        if let Err(e) = watcher.watch(&watch_dir, RecursiveMode::NonRecursive) {
+            eprintln!("Warning: Config hot reload disabled (watch error): {}", e);
+            return;
         }
-    }
-    // _clip drops here
-}
+        println!("Watching config file for changes: {:?}", path);
 
-fn set_clipboard_string_helper(text: &str) -> Result<()> {
-    let _clip = Clipboard::new_attempts(10)
-        .map_err(|e| anyhow!("Failed to open clipboard to set string: {}", e))?; // Map ErrorCode
+        let file_name = path.file_name().map(|name| name.to_os_string());
+        while let Ok(event) = fs_rx.recv() {
+            let Ok(event) = event else { continue };
+            if !event
+                .paths
+                .iter()
+                .any(|changed| changed.file_name().map(|n| n.to_os_string()) == file_name)
+            {
+                continue;
+            }
+            // Let the editor finish writing and collapse the burst of events it produces.
+            thread::sleep(Duration::from_millis(200));
+            while fs_rx.try_recv().is_ok() {}
 
-    clipboard_win::set_clipboard_string(text)
-        .map_err(|e| anyhow!("Failed to set clipboard string: {}", e)) // Map ErrorCode
-                                                                       // _clip drops here
-}
+            let file_config = match config::load(&path) {
+                Ok(file_config) => file_config,
+                Err(e) => {
+                    eprintln!("Config reload failed, keeping current settings: {:?}", e);
+                    continue;
+                }
+            };
 
-// --- process_clipboard_and_paste (Full Implementation) ---
-fn process_clipboard_and_paste(
-    original_content: ClipboardContent,
-    args: &Args,
-    rt: &Runtime,
-) -> Result<()> {
-    let mut _temp_audio_file_guard = None;
-    let mut _temp_image_file_guard = None;
+            let mut new_args = cli_args.clone();
+            new_args.file_config = file_config;
+            new_args.apply_file_config();
 
-    let processed_text_result = match &original_content {
-        ClipboardContent::FileList(files) => {
-            if files.len() == 1 {
-                let file_path = PathBuf::from(&files[0]);
-                let extension = file_path
-                    .extension()
-                    .and_then(|ext| ext.to_str())
-                    .map(|ext| ext.to_lowercase())
-                    .unwrap_or_default();
-
-                let audio_path_to_transcribe: PathBuf;
-
-                if AUDIO_EXTENSIONS.contains(&extension.as_str()) {
-                    println!("Detected single audio file: {:?}", file_path);
-                    audio_path_to_transcribe = file_path.clone();
-                } else if VIDEO_EXTENSIONS.contains(&extension.as_str()) {
-                    println!(
-                        "Detected single video file: {:?}. Extracting audio...",
-                        file_path
-                    );
-                    println!("INFO: Video processing requires ffmpeg in PATH.");
+            let mut current = shared_args.write().expect("settings lock poisoned");
+            if new_args.trigger_key != current.trigger_key {
+                println!(
+                    "Config reload: trigger_key change to {:?} ignored (restart required).",
+                    new_args.trigger_key
+                );
+                new_args.trigger_key = current.trigger_key;
+            }
+            *current = new_args;
+            println!(
+                "Config reloaded: lang={}, psm={:?}, beeps={}, paste_delay_ms={}, transcribe_model={}",
+                current.pipeline.lang,
+                current.pipeline.psm,
+                current.beeps,
+                current.paste_delay_ms,
+                current.pipeline.transcribe_model
+            );
+        }
+    });
+}
 
-                    let temp_audio_file = TempFileBuilder::new()
-                        .prefix("extracted_audio_")
-                        .suffix(".mp3")
-                        .tempfile_in(std::env::temp_dir())
-                        .with_context(|| "Failed to create temporary file for extracted audio")?;
+/// Runs `--watch-clipboard`: OCRs each new clipboard bitmap and replaces it with the
+/// text. There is no user action to paste into, so the text is left on the clipboard.
+fn spawn_clipboard_watcher(
+    shared_args: Arc<RwLock<Args>>,
+    enabled: Arc<AtomicBool>,
+    activity: Arc<Activity>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        // The monitor's hidden window belongs to the thread that creates it.
+        let mut monitor = match clipboard_win::Monitor::new() {
+            Ok(monitor) => monitor,
+            Err(e) => {
+                eprintln!("Error: Failed to start the clipboard watcher: {}", e);
+                return;
+            }
+        };
+        println!("Clipboard watcher started.");
+        // Some apps notify more than once per copy; handle each clipboard state once.
+        let mut handled_seq: Option<u32> = None;
 
-                    let temp_audio_path_obj = temp_audio_file.path().to_path_buf();
-                    _temp_audio_file_guard = Some(temp_audio_file);
+        loop {
+            match monitor.recv() {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(e) => {
+                    eprintln!("Error: Clipboard watcher stopped: {}", e);
+                    break;
+                }
+            }
+            let seq = clipboard_sequence_number();
+            if seq.is_some() && seq == handled_seq {
+                continue;
+            }
+            handled_seq = seq;
+            if seq == Some(OWN_CLIPBOARD_SEQ.load(Ordering::SeqCst)) {
+                continue; // Our own paste text or restored image.
+            }
+            if !enabled.load(Ordering::SeqCst)
+                || !clipboard_win::is_format_avail(formats::CF_BITMAP)
+            {
+                continue;
+            }
 
-                    println!(
-                        "Extracting audio via ffmpeg to temporary file: {:?}",
-                        temp_audio_path_obj
+            let _busy = activity.begin();
+            println!("\n--- New clipboard image (--watch-clipboard) ---");
+            let args = shared_args.read().expect("settings lock poisoned").clone();
+            let started = Instant::now();
+            let result = get_clipboard_content(&args).and_then(|content| match content {
+                // A file copy can carry a thumbnail bitmap; only plain images are OCR'd.
+                ClipboardContent::Bitmap(data) => {
+                    ocr_content(pipeline::Content::Image(data), &args).map(Some)
+                }
+                _ => Ok(None),
+            });
+            match result {
+                Ok(Some(text)) => {
+                    post_webhook(&args, "watcher", "image", &text);
+                    let text = finalize_text(&text, &args);
+                    let result_chars = text.trim().chars().count();
+                    if result_chars == 0 {
+                        println!("OCR found no text. Clipboard image left as is.");
+                        log_run(&args, "watcher", "image", started, 0, None);
+                        continue;
+                    }
+                    append_output_file(&text, &args);
+                    let set_result = set_clipboard_string_helper(&text, &args);
+                    log_run(
+                        &args,
+                        "watcher",
+                        "image",
+                        started,
+                        result_chars,
+                        set_result.as_ref().err(),
                     );
-                    let ffmpeg_output = Command::new("ffmpeg")
-                        .arg("-i")
-                        .arg(&file_path)
-                        .arg("-vn")
-                        .arg("-q:a")
-                        .arg("0")
-                        .arg("-y")
-                        .arg(&temp_audio_path_obj)
-                        .output()
-                        .with_context(|| {
-                            "Failed to execute ffmpeg command. Is ffmpeg installed and in PATH?"
-                        })?;
-
-                    if !ffmpeg_output.status.success() {
-                        let stderr = String::from_utf8_lossy(&ffmpeg_output.stderr);
-                        return Err(anyhow!(
-                            "ffmpeg failed to extract audio (Status: {}):\n{}",
-                            ffmpeg_output.status,
-                            stderr
-                        ));
+                    match set_result {
+                        Ok(()) => {
+                            println!(
+                                "Replaced the clipboard image with {} chars of text.",
+                                text.chars().count()
+                            );
+                        }
+                        Err(e) => {
+                            eprintln!("Error: Failed to set OCR text on the clipboard: {:?}", e)
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log_run(&args, "watcher", "image", started, 0, Some(&e));
+                    eprintln!("Error: Clipboard watcher OCR failed: {:?}", e);
+                    if args.beeps {
+                        play_sound(SoundType::Error);
                     }
-
-                    println!("Audio extraction successful.");
-                    audio_path_to_transcribe = temp_audio_path_obj;
-                } else {
-                    return Err(anyhow!(
-                        "Clipboard contains a single file, but it's not a supported audio or video format (Checked extensions: {:?}, {:?}, Found: {}).",
-                        AUDIO_EXTENSIONS, VIDEO_EXTENSIONS, extension
-                    ));
                 }
-
-                // Perform Transcription
-                let api_key = args.openai_api_key.as_ref().ok_or_else(|| {
-                    anyhow!("OpenAI API Key is missing (checked arg, .env, env var).")
-                })?;
-                let config = OpenAIConfig::new().with_api_key(api_key);
-                let client = Client::with_config(config);
-
-                let (tick_tx, tick_rx) = mpsc::channel();
-                let tick_handle = thread::spawn(move || tick_loop(tick_rx));
-
-                let transcription_result = rt.block_on(transcribe::trans::transcribe(
-                    &client,
-                    &audio_path_to_transcribe,
-                ));
-
-                let _ = tick_tx.send(());
-                let _ = tick_handle.join();
-
-                transcription_result
-                    .with_context(|| {
-                        format!(
-                            "Audio transcription failed for: {:?}",
-                            audio_path_to_transcribe
-                        )
-                    })
-                    .map_err(|e| {
-                        play_failure_sound();
-                        e
-                    })
-            } else {
-                Err(anyhow!(
-                    "Clipboard contains {} files. Only single audio/video file processing is supported.",
-                    files.len()
-                ))
             }
         }
-        ClipboardContent::Bitmap(bitmap_data) => {
-            println!("Processing clipboard image with Tesseract OCR...");
-            let temp_image_file = TempFileBuilder::new()
-                .prefix("clipboard_ocr_")
-                .suffix(".png")
-                .tempfile_in(std::env::temp_dir())
-                .with_context(|| "Failed to create temporary file for OCR image")?;
+        println!("Clipboard watcher finished.");
+    })
+}
 
-            let temp_image_path = temp_image_file.path().to_path_buf();
-            _temp_image_file_guard = Some(temp_image_file);
+/// Work handed to the worker thread.
+enum WorkerEvent {
+    /// A trigger or repeat key press/release from the keyboard hook.
+    Input(Event),
+    /// `--interval-secs` elapsed.
+    Interval,
+}
 
-            let img = image::load_from_memory(bitmap_data)
-                .with_context(|| "Failed to decode clipboard image data")?;
-            println!(
-                "Decoded image. Saving temporary PNG to {:?}",
-                temp_image_path
-            );
-            img.save_with_format(&temp_image_path, ImageFormat::Png)
-                .with_context(|| {
-                    format!(
-                        "Failed to save temporary PNG image to {:?}",
-                        temp_image_path
-                    )
-                })?;
-            println!("Temporary image saved.");
-
-            println!("Running Tesseract CLI...");
-            let mut command = Command::new(&args.tesseract_cmd);
-            command.arg(&temp_image_path);
-            command.arg("stdout");
-            command.arg("-l").arg(&args.lang);
-            if let Some(tessdata) = &args.tessdata_path {
-                command.arg("--tessdata-dir").arg(tessdata);
-            }
-            for arg in &args.tesseract_args {
-                command.arg(arg);
-            }
-
-            let output = command.output().map_err(|err| {
-                if err.kind() == std::io::ErrorKind::NotFound {
-                    anyhow!(
-                        "Tesseract command '{}' not found. Please install Tesseract and ensure it is in your PATH.",
-                        args.tesseract_cmd
-                    )
-                } else {
-                    anyhow!(
-                        "Failed to execute Tesseract command '{}': {}",
-                        args.tesseract_cmd,
-                        err
-                    )
-                }
-            })?;
-
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                Err(anyhow!(
-                    "Tesseract CLI failed (Status: {}):\n{}",
-                    output.status,
-                    stderr
-                ))
-            } else {
-                String::from_utf8(output.stdout)
-                    .with_context(|| "Tesseract output was not valid UTF-8")
-            }
+/// Sends `WorkerEvent::Interval` every `interval`, skipping a tick while the previous
+/// one is still queued.
+fn spawn_interval_timer(
+    interval: Duration,
+    event_tx: Sender<WorkerEvent>,
+    pending: Arc<AtomicBool>,
+) {
+    thread::spawn(move || loop {
+        thread::sleep(interval);
+        if pending.swap(true, Ordering::SeqCst) {
+            continue;
         }
-    };
+        if event_tx.send(WorkerEvent::Interval).is_err() {
+            break; // Worker finished.
+        }
+    });
+}
 
-    // Handle result of processing
-    match processed_text_result {
-        Ok(processed_text) => {
-            let trimmed_text = processed_text.trim();
-            if trimmed_text.is_empty() {
-                println!("Processing resulted in empty text. Skipping paste.");
-                restore_clipboard(original_content).with_context(|| {
-                    "Failed to restore original clipboard content after empty result"
-                })?;
-                // Still consider this a "success" in terms of overall operation completion,
-                // so Success beep might still be appropriate if enabled.
-                Ok(())
-            } else {
-                println!("Processed Text (first 100 chars): {:.100}...", trimmed_text);
+/// When the worker or the clipboard watcher last finished handling something, and how
+/// many of them are handling something now.
+struct Activity {
+    last: Mutex<Instant>,
+    busy: AtomicUsize,
+}
 
-                set_clipboard_string_helper(trimmed_text)
-                    .with_context(|| "Failed to place processed text onto clipboard")?;
-                println!("Processed text placed on clipboard. Simulating paste (Ctrl+V)...");
-                thread::sleep(Duration::from_millis(150));
-                send_ctrl_v().map_err(|e| anyhow!("Simulate Ctrl+V error: {}", e))?;
+/// Marks the work busy until dropped, then records the time.
+struct BusyGuard<'a>(&'a Activity);
 
-                thread::sleep(Duration::from_millis(150));
-                restore_clipboard(original_content)
-                    .with_context(|| "Failed to restore original content to clipboard")?;
-                println!("Original clipboard content restored.");
-                Ok(())
-            }
+impl Activity {
+    fn new() -> Self {
+        Activity {
+            last: Mutex::new(Instant::now()),
+            busy: AtomicUsize::new(0),
         }
-        Err(e) => {
-            eprintln!("ERROR processing clipboard content: {:?}", e);
-            if let Err(restore_err) = restore_clipboard(original_content) {
-                eprintln!(
-                    "Additionally failed to restore clipboard: {:?}",
-                    restore_err
-                );
-            }
-            Err(e) // Propagate the error
+    }
+
+    fn begin(&self) -> BusyGuard<'_> {
+        self.busy.fetch_add(1, Ordering::SeqCst);
+        BusyGuard(self)
+    }
+
+    fn idle_for(&self) -> Option<Duration> {
+        if self.busy.load(Ordering::SeqCst) > 0 {
+            return None;
         }
+        Some(self.last.lock().expect("activity lock poisoned").elapsed())
     }
-    // Temp guards drop here
 }
 
-// --- send_ctrl_v (Full Implementation) ---
-fn send_ctrl_v() -> Result<(), rdev::SimulateError> {
-    let delay = Duration::from_millis(30);
-    simulate(&EventType::KeyPress(Key::ControlLeft))?;
-    thread::sleep(delay);
-    simulate(&EventType::KeyPress(Key::KeyV))?;
-    thread::sleep(delay);
-    simulate(&EventType::KeyRelease(Key::KeyV))?;
-    thread::sleep(delay);
-    simulate(&EventType::KeyRelease(Key::ControlLeft))?;
-    println!("Paste simulated.");
-    Ok(())
+impl Drop for BusyGuard<'_> {
+    fn drop(&mut self) {
+        *self.0.last.lock().expect("activity lock poisoned") = Instant::now();
+        self.0.busy.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Signals `shutdown` once nothing has been handled for `idle` (`--idle-exit-secs`):
+/// the worker and the clipboard watcher are idle and no trigger is queued. The main
+/// thread then returns from `main`, ending the process with code 0.
+fn spawn_idle_exit(
+    idle: Duration,
+    activity: Arc<Activity>,
+    queued_triggers: Arc<AtomicUsize>,
+    shutdown: Sender<Result<()>>,
+) {
+    thread::spawn(move || loop {
+        thread::sleep(Duration::from_secs(1));
+        let Some(idle_for) = activity.idle_for() else {
+            continue;
+        };
+        if idle_for >= idle && queued_triggers.load(Ordering::SeqCst) == 0 {
+            println!(
+                "Nothing handled for {}s (--idle-exit-secs). Exiting.",
+                idle_for.as_secs()
+            );
+            let _ = shutdown.send(Ok(()));
+            return;
+        }
+    });
 }
 
 // --- Main Function (Conditional Sound Calls) ---
@@ -471,115 +2187,417 @@ fn main() -> Result<()> {
     };
 
     let mut args = Args::parse();
-    if args.openai_api_key.is_none() {
+    // Before the settings window, the paste simulation or any other window work.
+    if !window_info::set_dpi_awareness(args.dpi_awareness) {
+        println!(
+            "Note: Could not set DPI awareness to {:?}; keeping the process default.",
+            args.dpi_awareness
+        );
+    }
+    if args.pipeline.openai_api_key.is_none() {
         if let Ok(key) = env::var("OPENAI_API_KEY") {
             if !key.is_empty() {
-                args.openai_api_key = Some(key);
+                args.pipeline.openai_api_key = Some(key);
+            }
+        }
+    }
+    if args.pipeline.diarize_api_key.is_none() {
+        if let Ok(key) = env::var("DIARIZE_API_KEY") {
+            if !key.is_empty() {
+                args.pipeline.diarize_api_key = Some(key);
             }
         }
     }
 
-    let target_key: rdev::Key = args.trigger_key.into();
-    let args_clone_for_worker = args.clone(); // Clone includes the 'beeps' flag state
+    if args.signal_toggle {
+        control_event::signal_toggle(&args.toggle_event)?;
+        println!("Signaled toggle event '{}'.", args.toggle_event);
+        return Ok(());
+    }
+
+    SOUND_MIN_INTERVAL_MS.store(args.sound_min_interval_ms, Ordering::Relaxed);
+    SOUND_DISABLED.store(args.no_sound, Ordering::Relaxed);
+    tempfiles::set_prefix(&args.temp_prefix);
+    tempfiles::set_min_free_mb(args.min_free_mb);
+    if args.temp_sweep_age_secs > 0 {
+        let removed = tempfiles::sweep_stale(Duration::from_secs(args.temp_sweep_age_secs));
+        if removed > 0 {
+            println!("Removed {} stale temp file(s) from previous runs.", removed);
+        }
+    }
+    tempfiles::ensure_free_space(0).context("Refusing to start")?;
+
+    let mut config_path = args
+        .config
+        .clone()
+        .or_else(|| config::default_config_path().filter(|path| path.exists()));
+    let cli_args = args.clone();
+    if let Some(path) = &config_path {
+        args.file_config = config::load(path)?;
+        args.apply_file_config();
+        println!("Loaded config file: {:?}", path);
+    }
+
+    if args.settings {
+        let path = config_path
+            .clone()
+            .or_else(config::default_config_path)
+            .ok_or_else(|| anyhow!("No config path available. Pass --config <PATH>."))?;
+        if !settings_gui::run(&args, &path)? {
+            return Ok(());
+        }
+        args = cli_args.clone();
+        args.file_config = config::load(&path)?;
+        args.apply_file_config();
+        config_path = Some(path);
+    }
+
+    let sound_dir = config_path
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .or_else(|| {
+            config::default_config_path()?
+                .parent()
+                .map(Path::to_path_buf)
+        });
+    if let Some(dir) = sound_dir.filter(|_| !args.no_sound) {
+        load_sound_overrides(&dir);
+    }
+
+    if args.ocr_output_format != OcrOutputFormat::Text && args.output_file.is_none() {
+        return Err(anyhow!(
+            "--ocr-output-format {} needs --output-file to write to.",
+            args.ocr_output_format
+                .tesseract_config()
+                .unwrap_or_default()
+        ));
+    }
+
+    if args.self_test {
+        return self_test::run(&args);
+    }
+    if let Some(path) = &args.diagnose_image {
+        return diagnose::run(path, &args);
+    }
+    if let Some(spec) = &args.simulate_clipboard {
+        return simulate_clipboard::run(spec, &args);
+    }
+    if let Some(path) = &args.ocr_file {
+        let text = ocr_image_file(path, &args)?;
+        return emit_one_shot_text(&text, &args);
+    }
+    if let Some(path) = &args.transcribe_file {
+        let text = transcribe_audio_file(path, TranscriptSource::for_path(path), &args)?;
+        return emit_one_shot_text(&text, &args);
+    }
+
+    let shared_args = Arc::new(RwLock::new(args.clone()));
+    if let Some(path) = &config_path {
+        spawn_config_watcher(path.clone(), cli_args, Arc::clone(&shared_args));
+    }
+    let args_for_worker = Arc::clone(&shared_args);
+    let enabled = Arc::new(AtomicBool::new(true));
+    if let Err(e) = control_event::spawn_toggle_listener(&args.toggle_event, Arc::clone(&enabled)) {
+        eprintln!(
+            "Warning: Pause/resume via toggle event unavailable: {:?}",
+            e
+        );
+    }
+    let enabled_for_worker = Arc::clone(&enabled);
+
+    // Trigger presses sent to the worker but not yet picked up, capped by --max-queue.
+    let queued_triggers = Arc::new(AtomicUsize::new(0));
+    let activity = Arc::new(Activity::new());
+    // The keyboard hook can't be removed from another thread, so the main thread waits
+    // here for an idle exit or a listener failure and returns from `main` either way.
+    let (shutdown_tx, shutdown_rx) = mpsc::channel::<Result<()>>();
+    if let Some(idle_exit_secs) = args.idle_exit_secs {
+        spawn_idle_exit(
+            Duration::from_secs(idle_exit_secs),
+            Arc::clone(&activity),
+            Arc::clone(&queued_triggers),
+            shutdown_tx.clone(),
+        );
+    }
+
+    let watcher_handle = args.watch_clipboard.then(|| {
+        spawn_clipboard_watcher(
+            Arc::clone(&shared_args),
+            Arc::clone(&enabled),
+            Arc::clone(&activity),
+        )
+    });
+    if args.trigger_key.is_none() && args.interval_secs.is_none() {
+        if let Some(handle) = watcher_handle {
+            println!("No trigger key set. Only watching the clipboard for new images.");
+            if args.idle_exit_secs.is_some() {
+                return shutdown_rx.recv()?;
+            }
+            handle.join().expect("Clipboard watcher thread panicked");
+            return Ok(());
+        }
+        return Err(anyhow!("No trigger key set. Pass --trigger-key, set trigger_key in the config file, or run with --settings."));
+    }
+    let target_key: Option<rdev::Key> = args.trigger_key.map(Into::into);
+    let repeat_key: Option<rdev::Key> = args.repeat_key.map(Into::into);
+    if repeat_key.is_some() && repeat_key == target_key {
+        return Err(anyhow!("--repeat-key must differ from the trigger key."));
+    }
 
     // Startup Info
     println!("Clipboard Processor Started.");
-    println!(
-        "Trigger Key: {:?} (Converted to {:?})",
-        args.trigger_key, target_key
-    );
+    match (args.trigger_key, target_key) {
+        (Some(trigger_key), Some(target_key)) => println!(
+            "Trigger Key: {:?} (Converted to {:?})",
+            trigger_key, target_key
+        ),
+        _ => println!("Trigger Key: none"),
+    }
+    if let Some(interval_secs) = args.interval_secs {
+        println!(
+            "Interval: processing the clipboard every {}s",
+            interval_secs
+        );
+    }
+    if let Some(repeat_key) = args.repeat_key {
+        println!("Repeat Key: {:?} (re-pastes the last result)", repeat_key);
+    }
     println!("Optional Beeps Enabled: {}", args.beeps); // Log beep flag status
                                                         // ... (rest of startup messages) ...
-    if args.openai_api_key.is_some() { /* ... */
-    } else { /* ... */
+    if let Some(url) = &args.pipeline.diarize_backend {
+        println!(
+            "Diarization backend: {} (audio/video transcription with speaker labels).",
+            url
+        );
+    } else if args.pipeline.openai_api_key.is_some() {
+        println!("OpenAI API Key: Found (audio/video transcription enabled).");
+    } else {
+        println!("OpenAI API Key: Not found (audio/video transcription disabled).");
+    }
+    if args.check_key || args.require_key {
+        let check_result = match &args.pipeline.openai_api_key {
+            Some(key) => check_openai_key(key),
+            None => Err(anyhow!("No OpenAI API key configured.")),
+        };
+        match check_result {
+            Ok(()) => println!("OpenAI API Key check: valid."),
+            Err(e) if args.require_key => {
+                return Err(e.context("--require-key is set; refusing to start"));
+            }
+            Err(e) => eprintln!("Warning: {} (continuing; transcription may fail).", e),
+        }
     }
     println!("---");
-    println!(
-        "Press '{:?}' when an image OR a single audio/video file is in the clipboard to process.",
-        args.trigger_key
-    );
+    if let Some(trigger_key) = args.trigger_key {
+        println!(
+            "Press '{:?}' when an image OR a single audio/video file is in the clipboard to process.",
+            trigger_key
+        );
+    }
     // ...
 
-    let (event_tx, event_rx): (Sender<Event>, Receiver<Event>) = mpsc::channel();
+    let (event_tx, event_rx): (Sender<WorkerEvent>, Receiver<WorkerEvent>) = mpsc::channel();
+    // Set while an interval job waits in the queue, so a busy worker doesn't pile them up.
+    let interval_pending = Arc::new(AtomicBool::new(false));
+    if let Some(interval_secs) = args.interval_secs {
+        spawn_interval_timer(
+            Duration::from_secs(interval_secs),
+            event_tx.clone(),
+            Arc::clone(&interval_pending),
+        );
+    }
+    let queued_for_worker = Arc::clone(&queued_triggers);
+    let max_queue = args.max_queue as usize;
+    let activity_for_worker = Arc::clone(&activity);
 
     // Spawn Worker Thread (Conditional Beeps)
     let worker_handle = thread::spawn(move || {
         println!("Worker thread started.");
-        let rt = match Runtime::new() {
-            Ok(rt) => rt,
-            Err(e) => {
-                eprintln!(
-                    "FATAL: Failed to create Tokio runtime in worker thread: {}",
-                    e
-                );
-                return;
-            }
-        };
+        // Set while the trigger key is held in tap/hold mode (`--hold-threshold-ms`).
+        let mut held: Option<(Instant, Args, Option<record::Recording>)> = None;
+        // Clipboard sequence number the last interval job saw.
+        let mut interval_seq: Option<u32> = None;
 
-        for event in event_rx {
-            if let EventType::KeyPress(key) = event.event_type {
-                if key == target_key {
+        for worker_event in event_rx {
+            let event = match worker_event {
+                WorkerEvent::Input(event) => event,
+                WorkerEvent::Interval => {
+                    interval_pending.store(false, Ordering::SeqCst);
+                    if !enabled_for_worker.load(Ordering::SeqCst) || held.is_some() {
+                        continue;
+                    }
+                    let seq = clipboard_sequence_number();
+                    if seq.is_some()
+                        && (seq == interval_seq
+                            || seq == Some(OWN_CLIPBOARD_SEQ.load(Ordering::SeqCst)))
+                    {
+                        continue; // Unchanged since the last run, or our own write.
+                    }
+                    interval_seq = seq;
+                    let _busy = activity_for_worker.begin();
+                    println!("\n--- Interval elapsed (--interval-secs) ---");
+                    let args_clone_for_worker = args_for_worker
+                        .read()
+                        .expect("settings lock poisoned")
+                        .clone();
+                    let process_result = read_process_and_paste(&args_clone_for_worker);
+                    report_trigger_result(process_result, &args_clone_for_worker);
+                    continue;
+                }
+            };
+            // Mark busy before the queue count drops so the idle exit never sees both at zero.
+            let _busy = activity_for_worker.begin();
+            if let EventType::KeyPress(_) = event.event_type {
+                queued_for_worker.fetch_sub(1, Ordering::SeqCst);
+            }
+            match event.event_type {
+                EventType::KeyPress(key) if Some(key) == repeat_key => {
+                    println!("\n--- Repeat key pressed (received by worker) ---");
+                    if !enabled_for_worker.load(Ordering::SeqCst) {
+                        println!("Paused. Repeat ignored.");
+                        continue;
+                    }
+                    let args_clone_for_worker = args_for_worker
+                        .read()
+                        .expect("settings lock poisoned")
+                        .clone();
+                    if let Some(pattern) = ignored_app_match(&args_clone_for_worker) {
+                        println!(
+                            "Foreground app matches ignored pattern '{}'. Repeat ignored.",
+                            pattern
+                        );
+                        continue;
+                    }
+                    let repeat_result = repaste_last_result(&args_clone_for_worker);
+                    report_trigger_result(repeat_result, &args_clone_for_worker);
+                }
+                EventType::KeyPress(key) if Some(key) == target_key => {
+                    if held.is_some() {
+                        continue; // Key auto-repeat while held.
+                    }
                     println!("\n--- Trigger key pressed (received by worker) ---");
+                    if !enabled_for_worker.load(Ordering::SeqCst) {
+                        println!("Paused. Trigger ignored.");
+                        continue;
+                    }
+                    // Snapshot the settings so a config reload mid-run can't mix old and new values.
+                    let mut args_clone_for_worker = args_for_worker
+                        .read()
+                        .expect("settings lock poisoned")
+                        .clone();
+
+                    if let Some(pattern) = ignored_app_match(&args_clone_for_worker) {
+                        println!(
+                            "Foreground app matches ignored pattern '{}'. Trigger ignored.",
+                            pattern
+                        );
+                        continue;
+                    }
+                    if args_clone_for_worker.require_editable
+                        && !window_info::focused_control_has_caret()
+                    {
+                        println!("No editable text field is focused (--require-editable). Trigger ignored.");
+                        continue;
+                    }
+                    apply_app_language(&mut args_clone_for_worker);
+
+                    if args_clone_for_worker.hold_threshold_ms.is_some() {
+                        // Record from the start of the press so a hold doesn't lose its first words;
+                        // a tap simply discards the recording.
+                        let recording = record::Recording::start()
+                            .inspect_err(|e| eprintln!("Warning: Microphone unavailable: {:?}", e))
+                            .ok();
+                        held = Some((Instant::now(), args_clone_for_worker, recording));
+                        continue;
+                    }
 
                     // Play START sound only if flag is set
                     if args_clone_for_worker.beeps {
                         play_sound(SoundType::Start);
                     }
-
-                    let process_result = {
-                        match get_clipboard_content() {
-                            Ok(original_content) => process_clipboard_and_paste(
-                                original_content,
-                                &args_clone_for_worker,
-                                &rt,
-                            ),
-                            Err(e) => {
-                                eprintln!("ERROR getting clipboard content: {:?}", e);
-                                Err(e)
-                            }
-                        }
+                    let process_result = read_process_and_paste(&args_clone_for_worker);
+                    report_trigger_result(process_result, &args_clone_for_worker);
+                }
+                EventType::KeyRelease(key) if Some(key) == target_key => {
+                    let Some((pressed_at, held_args, recording)) = held.take() else {
+                        continue;
                     };
+                    let held_ms = pressed_at.elapsed().as_millis() as u64;
+                    let threshold_ms = held_args.hold_threshold_ms.unwrap_or_default();
 
-                    // Check result and play appropriate sound
-                    match process_result {
-                        Ok(_) => {
-                            // Play SUCCESS sound only if flag is set
-                            if args_clone_for_worker.beeps {
-                                play_sound(SoundType::Success);
-                            }
+                    if held_args.beeps {
+                        play_sound(SoundType::Start);
+                    }
+                    let process_result = match recording {
+                        Some(recording) if held_ms >= threshold_ms => {
+                            println!("Trigger held for {}ms. Transcribing microphone...", held_ms);
+                            transcribe_recording_and_paste(recording, &held_args)
                         }
-                        Err(e) => {
-                            // Always play ERROR sound
-                            play_sound(SoundType::Error);
-                            // Print error for visibility
-                            eprintln!("{}", e);
+                        None if held_ms >= threshold_ms => Err(anyhow!(
+                            "Trigger held for {}ms, but the microphone could not be recorded.",
+                            held_ms
+                        )),
+                        _ => {
+                            println!("Trigger tapped ({}ms). Processing clipboard...", held_ms);
+                            read_process_and_paste(&held_args)
                         }
-                    }
-
-                    println!("--- Worker ready for next trigger ---");
+                    };
+                    report_trigger_result(process_result, &held_args);
                 }
+                _ => {}
             }
         }
         println!("Worker thread finished.");
     });
 
+    if target_key.is_none() && repeat_key.is_none() {
+        println!("No trigger key set. Only processing the clipboard on the interval.");
+        if args.idle_exit_secs.is_some() {
+            return shutdown_rx.recv()?;
+        }
+        worker_handle.join().expect("Worker thread panicked");
+        return Ok(());
+    }
+
     // Setup and Run Keyboard Listener
     println!("Setting up keyboard listener...");
-    let callback = move |event: Event| {
-        let _ = event_tx.send(event);
+    // Only the trigger and repeat keys matter to the worker; other input never leaves the hook.
+    let callback = move |event: Event| match event.event_type {
+        EventType::KeyPress(key) if Some(key) == target_key || Some(key) == repeat_key => {
+            if queued_triggers.load(Ordering::SeqCst) >= max_queue {
+                println!(
+                    "Worker is busy with {} queued trigger(s). Trigger dropped (--max-queue).",
+                    max_queue
+                );
+                return;
+            }
+            queued_triggers.fetch_add(1, Ordering::SeqCst);
+            let _ = event_tx.send(WorkerEvent::Input(event));
+        }
+        EventType::KeyRelease(key) if Some(key) == target_key => {
+            let _ = event_tx.send(WorkerEvent::Input(event));
+        }
+        _ => {}
     };
 
-    if let Err(error) = listen(callback) {
-        eprintln!(
-            "FATAL ERROR setting up global keyboard listener: {:?}",
-            error
-        );
-        eprintln!("This might be a permissions issue. Try running as administrator.");
-        return Err(anyhow!("Keyboard listener setup failed: {:?}", error));
-    }
-
-    // Optional: Join worker handle
-    worker_handle.join().expect("Worker thread panicked");
+    thread::spawn(move || {
+        if let Err(error) = listen(callback) {
+            eprintln!(
+                "FATAL ERROR setting up global keyboard listener: {:?}",
+                error
+            );
+            eprintln!("This might be a permissions issue. Try running as administrator.");
+            let _ = shutdown_tx.send(Err(anyhow!("Keyboard listener setup failed: {:?}", error)));
+        }
+    });
 
-    Ok(())
+    match shutdown_rx.recv() {
+        Ok(result) => result,
+        Err(_) => {
+            // The listener returned on its own, closing the event channel.
+            worker_handle.join().expect("Worker thread panicked");
+            Ok(())
+        }
+    }
 }
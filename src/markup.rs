@@ -0,0 +1,291 @@
+// src/markup.rs
+// Plain-text extraction from the clipboard's rich text formats (CF_HTML and RTF).
+
+/// Returns the copied fragment of a CF_HTML payload, using the `StartFragment`/`EndFragment`
+/// byte offsets from its header. Falls back to the whole payload if the header is unusable.
+pub fn html_clipboard_fragment(raw: &[u8]) -> String {
+    let text = String::from_utf8_lossy(raw);
+    let offset = |name: &str| -> Option<usize> {
+        let line = text.lines().find(|line| line.starts_with(name))?;
+        line[name.len()..].trim().parse().ok()
+    };
+    match (offset("StartFragment:"), offset("EndFragment:")) {
+        (Some(start), Some(end)) if start <= end && end <= raw.len() => {
+            String::from_utf8_lossy(&raw[start..end]).into_owned()
+        }
+        _ => text.trim_end_matches('\0').to_string(),
+    }
+}
+
+/// Strips tags from HTML, turning block-level elements into line breaks and
+/// decoding character entities. Script, style and comment contents are dropped.
+pub fn html_to_text(html: &str) -> String {
+    const BLOCK_TAGS: &[&str] = &[
+        "br",
+        "p",
+        "div",
+        "li",
+        "tr",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "pre",
+        "blockquote",
+        "table",
+        "ul",
+        "ol",
+    ];
+
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(tag_start) = rest.find('<') {
+        text.push_str(&decode_entities(&rest[..tag_start]));
+        rest = &rest[tag_start..];
+
+        if rest.starts_with("<!--") {
+            rest = rest.find("-->").map_or("", |end| &rest[end + 3..]);
+            continue;
+        }
+        let Some(tag_end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..tag_end];
+        rest = &rest[tag_end + 1..];
+
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        if (name == "script" || name == "style") && !tag.starts_with('/') {
+            let closing = format!("</{}", name);
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&closing)
+                .map_or("", |end| &rest[end..]);
+            continue;
+        }
+        if name == "td" || name == "th" {
+            if !tag.starts_with('/') && !text.is_empty() && !text.ends_with('\n') {
+                text.push('\t');
+            }
+        } else if BLOCK_TAGS.contains(&name.as_str()) && !text.ends_with('\n') {
+            text.push('\n');
+        }
+    }
+    text.push_str(&decode_entities(rest));
+    collapse_blank_lines(&text)
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest
+            .find(';')
+            .filter(|&end| end <= 10)
+            .map(|end| (&rest[1..end], end));
+        let replacement = entity.and_then(|(name, _)| match name {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ if name.starts_with("#x") || name.starts_with("#X") => {
+                u32::from_str_radix(&name[2..], 16)
+                    .ok()
+                    .and_then(char::from_u32)
+            }
+            _ if name.starts_with('#') => name[1..].parse().ok().and_then(char::from_u32),
+            _ => None,
+        });
+        match (replacement, entity) {
+            (Some(c), Some((_, end))) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Extracts the visible text of an RTF document: paragraphs and line breaks become
+/// newlines, `\'hh` and `\uN` escapes are decoded, and non-text destinations
+/// (font/color tables, `{\*...}` groups, pictures) are skipped.
+pub fn rtf_to_text(rtf: &str) -> String {
+    const SKIPPED_DESTINATIONS: &[&str] = &[
+        "fonttbl",
+        "colortbl",
+        "stylesheet",
+        "info",
+        "pict",
+        "header",
+        "footer",
+        "listtable",
+        "listoverridetable",
+        "themedata",
+        "datastore",
+        "latentstyles",
+    ];
+
+    let mut text = String::new();
+    // For each open group: whether its content is skipped.
+    let mut skip_stack: Vec<bool> = vec![false];
+    // Characters to drop after a \uN escape (its ANSI fallback).
+    let mut pending_fallback = 0usize;
+    let chars: Vec<char> = rtf.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let skipping = *skip_stack.last().unwrap_or(&false);
+        match chars[i] {
+            '{' => {
+                skip_stack.push(skipping);
+                i += 1;
+            }
+            '}' => {
+                if skip_stack.len() > 1 {
+                    skip_stack.pop();
+                }
+                i += 1;
+            }
+            '\\' if i + 1 < chars.len() => {
+                let next = chars[i + 1];
+                if next == '*' {
+                    if let Some(top) = skip_stack.last_mut() {
+                        *top = true;
+                    }
+                    i += 2;
+                } else if next == '\'' && i + 3 < chars.len() {
+                    let hex: String = chars[i + 2..i + 4].iter().collect();
+                    if let Ok(byte) = u8::from_str_radix(&hex, 16) {
+                        if pending_fallback > 0 {
+                            pending_fallback -= 1;
+                        } else if !skipping {
+                            // Windows-1252 approximated as Latin-1.
+                            text.push(byte as char);
+                        }
+                    }
+                    i += 4;
+                } else if next.is_ascii_alphabetic() {
+                    let start = i + 1;
+                    let mut end = start;
+                    while end < chars.len() && chars[end].is_ascii_alphabetic() {
+                        end += 1;
+                    }
+                    let word: String = chars[start..end].iter().collect();
+                    let param_start = end;
+                    if end < chars.len() && chars[end] == '-' {
+                        end += 1;
+                    }
+                    while end < chars.len() && chars[end].is_ascii_digit() {
+                        end += 1;
+                    }
+                    let param: Option<i32> = chars[param_start..end]
+                        .iter()
+                        .collect::<String>()
+                        .parse()
+                        .ok();
+                    // A single space terminates a control word and is consumed with it.
+                    if end < chars.len() && chars[end] == ' ' {
+                        end += 1;
+                    }
+                    i = end;
+
+                    if SKIPPED_DESTINATIONS.contains(&word.as_str()) {
+                        if let Some(top) = skip_stack.last_mut() {
+                            *top = true;
+                        }
+                        continue;
+                    }
+                    if skipping {
+                        continue;
+                    }
+                    match word.as_str() {
+                        "par" | "line" | "row" => text.push('\n'),
+                        "tab" | "cell" => text.push('\t'),
+                        "emdash" => text.push('\u{2014}'),
+                        "endash" => text.push('\u{2013}'),
+                        "lquote" => text.push('\u{2018}'),
+                        "rquote" => text.push('\u{2019}'),
+                        "ldblquote" => text.push('\u{201C}'),
+                        "rdblquote" => text.push('\u{201D}'),
+                        "bullet" => text.push('\u{2022}'),
+                        "u" => {
+                            if let Some(code) = param {
+                                let code = if code < 0 { code + 65536 } else { code } as u32;
+                                if let Some(c) = char::from_u32(code) {
+                                    text.push(c);
+                                }
+                                pending_fallback = 1;
+                            }
+                        }
+                        _ => {}
+                    }
+                } else {
+                    // Escaped literal: \\ \{ \} (and \~ as a non-breaking space).
+                    if !skipping {
+                        match next {
+                            '~' => text.push(' '),
+                            '\\' | '{' | '}' => text.push(next),
+                            _ => {}
+                        }
+                    }
+                    i += 2;
+                }
+            }
+            '\r' | '\n' => i += 1,
+            c => {
+                if pending_fallback > 0 {
+                    pending_fallback -= 1;
+                } else if !skipping {
+                    text.push(c);
+                }
+                i += 1;
+            }
+        }
+    }
+    collapse_blank_lines(&text)
+}
+
+/// Trims trailing spaces on each line and collapses runs of blank lines to one.
+fn collapse_blank_lines(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && lines.last().is_some_and(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+/// Wraps plain text as an HTML fragment, escaping markup and keeping line breaks.
+pub fn text_to_html(text: &str) -> String {
+    let mut html = String::with_capacity(text.len() + 16);
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\n' => html.push_str("<br>"),
+            '\r' => {}
+            _ => html.push(c),
+        }
+    }
+    html
+}
@@ -0,0 +1,599 @@
+// src/ocr.rs
+// Image preparation and the Tesseract CLI runs behind every OCR result.
+
+use crate::{hocr, image_ops, pipeline::ProgressCallback, tempfiles};
+use anyhow::{anyhow, Context, Result};
+use image::codecs::png::{CompressionType, FilterType as PngFilterType, PngEncoder};
+use image::{DynamicImage, ImageFormat};
+use std::{path::Path, process::Command};
+use tempfile::NamedTempFile;
+
+/// Tesseract's orientation confidence below which `auto_rotate` leaves an image alone.
+const AUTO_ROTATE_MIN_CONFIDENCE: f32 = 1.0;
+/// `auto_invert` flips images whose mean luminance is below this (of 255).
+const AUTO_INVERT_MAX_LUMINANCE: f64 = 128.0;
+
+/// File format of the temporary image handed to Tesseract.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TempImageFormat {
+    /// PNG with the fastest compression (the file is deleted right after OCR).
+    Png,
+    /// Uncompressed BMP: larger, but quickest to write for big images.
+    Bmp,
+}
+
+/// How images are prepared and how Tesseract is invoked.
+#[derive(Debug, Clone)]
+pub struct OcrSettings {
+    pub tesseract_cmd: String,
+    pub tessdata_path: Option<String>,
+    /// Tesseract language(s), e.g. `eng` or `eng+deu`.
+    pub lang: String,
+    pub psm: Option<u32>,
+    /// Page segmentation modes retried in order while the result is empty.
+    pub psm_fallback: Vec<u32>,
+    /// Turn each image upright with Tesseract's orientation detection before OCR.
+    pub auto_rotate: bool,
+    /// Crop to the region drawn in this color before OCR.
+    pub color_mask: Option<image_ops::ColorMask>,
+    /// Invert colors, for light text on a dark background.
+    pub invert: bool,
+    /// Invert only mostly dark images.
+    pub auto_invert: bool,
+    /// Hand PNG data to Tesseract as is, skipping decode, re-encode and the blank check.
+    pub fast_image: bool,
+    /// Resolution Tesseract assumes for the image; 0 lets it estimate.
+    pub dpi: u32,
+    /// Passed through to Tesseract after the other arguments.
+    pub extra_args: Vec<String>,
+    /// Program run as `<program> <image> <output>` on the prepared image before Tesseract.
+    pub pre_command: Option<String>,
+    /// Reject images whose header declares more pixels than this (0 for no limit).
+    pub max_pixels: u64,
+    /// How transparency is handled before OCR.
+    pub flatten_alpha: image_ops::FlattenAlpha,
+    /// Images narrower or shorter than this are rejected (0 disables).
+    pub min_image_dimension: u32,
+    /// Luminance/alpha variance below which an image is blank (0 disables).
+    pub blank_variance_threshold: f64,
+    pub temp_image_format: TempImageFormat,
+    /// Read hOCR and convert it to Markdown instead of plain text.
+    pub markdown: bool,
+    /// Prepend a line with the detected script and orientation.
+    pub include_osd: bool,
+    /// Decodes Tesseract output that isn't valid UTF-8.
+    pub encoding: Option<&'static encoding_rs::Encoding>,
+    /// Replace invalid UTF-8 instead of failing, when `encoding` isn't set.
+    pub lossy_utf8: bool,
+    /// Use the text of a failed Tesseract run if it printed any.
+    pub accept_partial: bool,
+    /// Log Tesseract's stderr even when it succeeds.
+    pub show_warnings: bool,
+    /// Replaces the form feeds between pages of multi-page input.
+    pub page_separator: String,
+}
+
+impl Default for OcrSettings {
+    fn default() -> Self {
+        OcrSettings {
+            tesseract_cmd: "tesseract".to_string(),
+            tessdata_path: None,
+            lang: "eng".to_string(),
+            psm: None,
+            psm_fallback: Vec::new(),
+            auto_rotate: false,
+            color_mask: None,
+            invert: false,
+            auto_invert: false,
+            fast_image: false,
+            dpi: 300,
+            extra_args: Vec::new(),
+            pre_command: None,
+            max_pixels: 100_000_000,
+            flatten_alpha: image_ops::FlattenAlpha::Auto,
+            min_image_dimension: 16,
+            blank_variance_threshold: 2.0,
+            temp_image_format: TempImageFormat::Png,
+            markdown: false,
+            include_osd: false,
+            encoding: None,
+            lossy_utf8: false,
+            accept_partial: false,
+            show_warnings: false,
+            page_separator: "\n\n".to_string(),
+        }
+    }
+}
+
+/// Full OCR of in-memory image data. The temporary image is removed on return.
+pub fn recognize(
+    image_data: &[u8],
+    settings: &OcrSettings,
+    progress: &mut dyn ProgressCallback,
+) -> Result<String> {
+    let mut temp_image_file = prepare_image(image_data, settings)?;
+    if let Some(program) = &settings.pre_command {
+        temp_image_file = run_pre_command(program, temp_image_file)?;
+    }
+    progress.on_image_prepared(temp_image_file.path());
+    let mut text = if settings.markdown {
+        markdown(temp_image_file.path(), settings)?
+    } else {
+        run_tesseract_with_fallback(temp_image_file.path(), settings)?
+    };
+    if settings.include_osd && !text.trim().is_empty() {
+        if let Some(line) = osd_metadata_line(temp_image_file.path(), settings) {
+            text = format!("{}\n{}", line, text);
+        }
+    }
+    Ok(text)
+}
+
+/// `pre_command`: runs `<program> <image> <output>` for custom cleanup. Uses `<output>`
+/// if the program wrote it, otherwise `<image>` (edited in place). When the program
+/// fails, the image is restored and OCR goes on with the original.
+fn run_pre_command(program: &str, image_file: NamedTempFile) -> Result<NamedTempFile> {
+    let suffix = image_file
+        .path()
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let output_file = tempfiles::file("pre_command", &suffix)?;
+    let original = std::fs::read(image_file.path())
+        .with_context(|| format!("Failed to read {:?}", image_file.path()))?;
+
+    println!("Running --pre-command '{}'...", program);
+    let failure = match Command::new(program)
+        .arg(image_file.path())
+        .arg(output_file.path())
+        .output()
+    {
+        Ok(output) if output.status.success() => None,
+        Ok(output) => Some(format!(
+            "exited with {}:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )),
+        Err(e) => Some(e.to_string()),
+    };
+    if let Some(failure) = failure {
+        eprintln!(
+            "Warning: --pre-command '{}' failed, using the original image: {}",
+            program, failure
+        );
+        std::fs::write(image_file.path(), &original)
+            .with_context(|| format!("Failed to restore {:?}", image_file.path()))?;
+        return Ok(image_file);
+    }
+
+    let wrote_output = std::fs::metadata(output_file.path()).is_ok_and(|meta| meta.len() > 0);
+    if wrote_output {
+        println!("--pre-command wrote {:?}.", output_file.path());
+        Ok(output_file)
+    } else {
+        println!("--pre-command finished; using the image it edited in place.");
+        Ok(image_file)
+    }
+}
+
+/// Writes the OCR input quickly: PNG at the fastest compression level, or plain BMP.
+fn save_temp_image(img: &DynamicImage, path: &Path, format: TempImageFormat) -> Result<()> {
+    match format {
+        TempImageFormat::Png => {
+            let file = std::fs::File::create(path)?;
+            let encoder = PngEncoder::new_with_quality(
+                std::io::BufWriter::new(file),
+                CompressionType::Fast,
+                PngFilterType::Adaptive,
+            );
+            img.write_with_encoder(encoder)?;
+        }
+        TempImageFormat::Bmp => img.save_with_format(path, ImageFormat::Bmp)?,
+    }
+    Ok(())
+}
+
+/// Decodes the image data, rejects blank images and saves it as a temporary image for Tesseract.
+pub fn prepare_image(image_data: &[u8], settings: &OcrSettings) -> Result<NamedTempFile> {
+    image_ops::check_pixel_count(image_data, settings.max_pixels)?;
+    let transforms = settings.auto_rotate
+        || settings.color_mask.is_some()
+        || settings.invert
+        || settings.auto_invert
+        || settings.flatten_alpha == image_ops::FlattenAlpha::Always;
+    if settings.fast_image && !transforms {
+        if let Some(temp_image_file) = write_png_as_is(image_data, settings)? {
+            return Ok(temp_image_file);
+        }
+    }
+    let suffix = match settings.temp_image_format {
+        TempImageFormat::Png => ".png",
+        TempImageFormat::Bmp => ".bmp",
+    };
+    let temp_image_file = tempfiles::file("clipboard_ocr", suffix)?;
+    let temp_image_path = temp_image_file.path().to_path_buf();
+
+    let mut img = image::load_from_memory(image_data)
+        .with_context(|| "Failed to decode clipboard image data")?;
+    if let Some((flattened, action)) = image_ops::flatten_alpha(&img, settings.flatten_alpha) {
+        println!("Flattened image alpha: {} (--flatten-alpha).", action);
+        img = flattened;
+    }
+    if let Some(mask) = settings.color_mask {
+        img = image_ops::crop_to_color(&img, mask).ok_or_else(|| {
+            anyhow!(
+                "No pixels match --color-mask #{:02x}{:02x}{:02x} (tolerance {}).",
+                mask.rgb[0],
+                mask.rgb[1],
+                mask.rgb[2],
+                mask.tolerance
+            )
+        })?;
+        println!(
+            "Cropped to the --color-mask region: {}x{}.",
+            img.width(),
+            img.height()
+        );
+    }
+    if img.width() < settings.min_image_dimension || img.height() < settings.min_image_dimension {
+        return Err(anyhow!(
+            "Image too small to OCR ({}x{}, --min-image-dimension is {}px). Likely a placeholder image; skipping Tesseract.",
+            img.width(),
+            img.height(),
+            settings.min_image_dimension
+        ));
+    }
+    if is_effectively_blank(&img, settings.blank_variance_threshold) {
+        return Err(anyhow!(
+            "Clipboard image appears blank (uniform or fully transparent, {}x{}). Skipping OCR.",
+            img.width(),
+            img.height()
+        ));
+    }
+    if settings.invert {
+        println!("Inverting image colors (--invert).");
+        img.invert();
+    } else if settings.auto_invert {
+        let luminance = image_ops::mean_luminance(&img);
+        if luminance < AUTO_INVERT_MAX_LUMINANCE {
+            println!(
+                "Mostly dark image (mean luminance {:.0}). Inverting colors (--auto-invert).",
+                luminance
+            );
+            img.invert();
+        }
+    }
+    let img = if settings.auto_rotate {
+        auto_rotate(img, settings)?
+    } else {
+        img
+    };
+    // Uncompressed RGBA is the upper bound for both PNG and BMP.
+    tempfiles::ensure_free_space(img.width() as u64 * img.height() as u64 * 4)?;
+    println!(
+        "Decoded image. Saving temporary {:?} to {:?}",
+        settings.temp_image_format, temp_image_path
+    );
+    save_temp_image(&img, &temp_image_path, settings.temp_image_format).with_context(|| {
+        format!(
+            "Failed to save temporary OCR image to {:?}",
+            temp_image_path
+        )
+    })?;
+    println!("Temporary image saved.");
+    Ok(temp_image_file)
+}
+
+/// `--fast-image`: hands PNG data to Tesseract byte for byte, skipping the decode and
+/// re-encode (and with them the blank-image check). `None` for anything else.
+fn write_png_as_is(image_data: &[u8], settings: &OcrSettings) -> Result<Option<NamedTempFile>> {
+    const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+    // The IHDR chunk, with the width, height, bit depth and color type, always comes first.
+    if !image_data.starts_with(PNG_MAGIC) || image_data.len() < 26 || &image_data[12..16] != b"IHDR"
+    {
+        return Ok(None);
+    }
+    let dimension = |at: usize| u32::from_be_bytes(image_data[at..at + 4].try_into().unwrap());
+    let (width, height) = (dimension(16), dimension(20));
+    if width < settings.min_image_dimension || height < settings.min_image_dimension {
+        return Ok(None); // Let the decode path report it.
+    }
+    // Color types 4 (gray + alpha) and 6 (RGBA) carry an alpha channel, which may be
+    // the bogus all-zero one that `flatten_alpha` drops after decoding.
+    let has_alpha = matches!(image_data[25], 4 | 6);
+    if has_alpha && settings.flatten_alpha != image_ops::FlattenAlpha::Never {
+        return Ok(None);
+    }
+
+    let temp_image_file = tempfiles::file("clipboard_ocr", ".png")?;
+    std::fs::write(temp_image_file.path(), image_data).with_context(|| {
+        format!(
+            "Failed to save temporary OCR image to {:?}",
+            temp_image_file.path()
+        )
+    })?;
+    println!(
+        "Image is already PNG ({}x{}). Passing it to Tesseract without re-encoding (--fast-image).",
+        width, height
+    );
+    Ok(Some(temp_image_file))
+}
+
+/// Returns the pixel variance of the image's luminance and alpha channels.
+/// Both are near zero for all-white, all-black or fully transparent images.
+fn image_variance(img: &DynamicImage) -> (f64, f64) {
+    let rgba = img.to_rgba8();
+    let count = (rgba.width() as f64) * (rgba.height() as f64);
+    if count == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let (mut luma_sum, mut luma_sq_sum) = (0.0f64, 0.0f64);
+    let (mut alpha_sum, mut alpha_sq_sum) = (0.0f64, 0.0f64);
+    for pixel in rgba.pixels() {
+        let [r, g, b, a] = pixel.0;
+        let luma = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+        luma_sum += luma;
+        luma_sq_sum += luma * luma;
+        alpha_sum += a as f64;
+        alpha_sq_sum += (a as f64) * (a as f64);
+    }
+
+    let luma_mean = luma_sum / count;
+    let alpha_mean = alpha_sum / count;
+    (
+        luma_sq_sum / count - luma_mean * luma_mean,
+        alpha_sq_sum / count - alpha_mean * alpha_mean,
+    )
+}
+
+/// An image is effectively blank when neither its colors nor its alpha
+/// channel carry enough variation for Tesseract to find text in.
+fn is_effectively_blank(img: &DynamicImage, threshold: f64) -> bool {
+    if threshold <= 0.0 {
+        return false;
+    }
+    let (luma_variance, alpha_variance) = image_variance(img);
+    println!(
+        "Image variance: luminance {:.2}, alpha {:.2} (blank threshold {:.2})",
+        luma_variance, alpha_variance, threshold
+    );
+    luma_variance < threshold && alpha_variance < threshold
+}
+
+/// Runs the Tesseract CLI on an image file and returns its stdout text.
+fn run_tesseract(image_path: &Path, settings: &OcrSettings, psm: Option<u32>) -> Result<String> {
+    println!("Running Tesseract CLI...");
+    run_tesseract_command(tesseract_command(image_path, settings, psm), settings)
+}
+
+/// Rotates the image upright using Tesseract's orientation detection (`--psm 0`).
+/// Detection failures and low-confidence guesses leave the image as it is.
+fn auto_rotate(img: DynamicImage, settings: &OcrSettings) -> Result<DynamicImage> {
+    let osd_image = tempfiles::file("osd", ".png")?;
+    save_temp_image(&img, osd_image.path(), TempImageFormat::Png)?;
+    let osd = match run_tesseract(osd_image.path(), settings, Some(0)) {
+        Ok(osd) => osd,
+        Err(e) => {
+            println!(
+                "Auto-rotate: orientation detection failed, keeping the image as is: {}",
+                e
+            );
+            return Ok(img);
+        }
+    };
+    let Some(OsdInfo {
+        rotate,
+        orientation_confidence: confidence,
+        ..
+    }) = parse_osd(&osd)
+    else {
+        println!("Auto-rotate: no orientation reported, keeping the image as is.");
+        return Ok(img);
+    };
+    if rotate == 0 {
+        println!(
+            "Auto-rotate: image is upright (confidence {:.2}).",
+            confidence
+        );
+        return Ok(img);
+    }
+    if confidence < AUTO_ROTATE_MIN_CONFIDENCE {
+        println!(
+            "Auto-rotate: {} degrees suggested, but confidence {:.2} is too low. Keeping the image as is.",
+            rotate, confidence
+        );
+        return Ok(img);
+    }
+    println!(
+        "Auto-rotate: rotating {} degrees clockwise (confidence {:.2}).",
+        rotate, confidence
+    );
+    Ok(match rotate {
+        90 => img.rotate90(),
+        180 => img.rotate180(),
+        270 => img.rotate270(),
+        _ => img,
+    })
+}
+
+/// Orientation and script detection from a Tesseract `--psm 0` pass.
+struct OsdInfo {
+    /// Clockwise degrees that turn the image upright.
+    rotate: u32,
+    /// Degrees the text is currently turned.
+    orientation_degrees: u32,
+    orientation_confidence: f32,
+    script: Option<String>,
+    script_confidence: Option<f32>,
+}
+
+/// Reads Tesseract's OSD output. `None` unless it reports an orientation.
+fn parse_osd(osd: &str) -> Option<OsdInfo> {
+    let field = |name: &str| {
+        osd.lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(str::trim)
+    };
+    Some(OsdInfo {
+        rotate: field("Rotate:")?.parse().ok()?,
+        orientation_degrees: field("Orientation in degrees:")?.parse().ok()?,
+        orientation_confidence: field("Orientation confidence:")?.parse().ok()?,
+        script: field("Script:").map(str::to_string),
+        script_confidence: field("Script confidence:").and_then(|value| value.parse().ok()),
+    })
+}
+
+/// The `include_osd` line put before the OCR text, or `None` if detection failed.
+fn osd_metadata_line(image_path: &Path, settings: &OcrSettings) -> Option<String> {
+    let osd = match run_tesseract(image_path, settings, Some(0)) {
+        Ok(osd) => osd,
+        Err(e) => {
+            println!("Warning: Orientation/script detection failed: {}", e);
+            return None;
+        }
+    };
+    let Some(info) = parse_osd(&osd) else {
+        println!("Warning: Tesseract reported no orientation/script information.");
+        return None;
+    };
+    Some(format!(
+        "[OSD] script: {} (confidence {:.2}), orientation: {} degrees (confidence {:.2})",
+        info.script.as_deref().unwrap_or("unknown"),
+        info.script_confidence.unwrap_or(0.0),
+        info.orientation_degrees,
+        info.orientation_confidence
+    ))
+}
+
+/// Builds the Tesseract invocation that writes the result for `image_path` to stdout.
+/// Append a config name such as `tsv` or `hocr` for output other than plain text.
+pub fn tesseract_command(image_path: &Path, settings: &OcrSettings, psm: Option<u32>) -> Command {
+    let mut command = Command::new(&settings.tesseract_cmd);
+    command.arg(image_path);
+    command.arg("stdout");
+    command.arg("-l").arg(&settings.lang);
+    if let Some(psm) = psm {
+        command.arg("--psm").arg(psm.to_string());
+    }
+    if settings.dpi > 0 {
+        command.arg("--dpi").arg(settings.dpi.to_string());
+    }
+    if let Some(tessdata) = &settings.tessdata_path {
+        command.arg("--tessdata-dir").arg(tessdata);
+    }
+    for arg in &settings.extra_args {
+        command.arg(arg);
+    }
+    command
+}
+
+/// Runs a command from `tesseract_command` and returns its stdout.
+pub fn run_tesseract_command(mut command: Command, settings: &OcrSettings) -> Result<String> {
+    let output = command.output().map_err(|err| {
+        if err.kind() == std::io::ErrorKind::NotFound {
+            anyhow!(
+                "Tesseract command '{}' not found. Please install Tesseract and ensure it is in your PATH.",
+                settings.tesseract_cmd
+            )
+        } else {
+            anyhow!(
+                "Failed to execute Tesseract command '{}': {}",
+                settings.tesseract_cmd,
+                err
+            )
+        }
+    })?;
+
+    let has_output = output.stdout.iter().any(|b| !b.is_ascii_whitespace());
+    if !output.status.success() && settings.accept_partial && has_output {
+        println!(
+            "Warning: Tesseract exited with {} but produced text; using it (--accept-partial-ocr). Stderr:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        );
+        decode_output(output.stdout, settings)
+    } else if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(anyhow!(
+            "Tesseract CLI failed (Status: {}):\n{}",
+            output.status,
+            stderr
+        ))
+    } else {
+        if settings.show_warnings {
+            report_warnings(&String::from_utf8_lossy(&output.stderr));
+        }
+        decode_output(output.stdout, settings)
+    }
+}
+
+/// Decodes Tesseract's stdout as UTF-8, falling back to `encoding` or, with
+/// `lossy_utf8`, replacement characters.
+fn decode_output(stdout: Vec<u8>, settings: &OcrSettings) -> Result<String> {
+    let error = match String::from_utf8(stdout) {
+        Ok(text) => return Ok(text),
+        Err(error) => error,
+    };
+    if let Some(encoding) = settings.encoding {
+        println!(
+            "Tesseract output was not valid UTF-8. Decoding it as {}.",
+            encoding.name()
+        );
+        let (text, _, _) = encoding.decode(error.as_bytes());
+        return Ok(text.into_owned());
+    }
+    if settings.lossy_utf8 {
+        println!("Warning: Tesseract output was not valid UTF-8. Replacing invalid bytes.");
+        return Ok(String::from_utf8_lossy(error.as_bytes()).into_owned());
+    }
+    Err(anyhow!(
+        "Tesseract output was not valid UTF-8 ({}). Set --ocr-encoding (e.g. latin1) or --lossy-utf8.",
+        error.utf8_error()
+    ))
+}
+
+/// Logs what Tesseract printed to stderr on a successful run, with a hint for
+/// resolution warnings (a common cause of poor OCR).
+fn report_warnings(stderr: &str) {
+    let stderr = stderr.trim();
+    if stderr.is_empty() {
+        return;
+    }
+    println!("Tesseract warnings:\n{}", stderr);
+    let lower = stderr.to_lowercase();
+    if lower.contains("resolution") || lower.contains("dpi") {
+        println!(
+            "Hint: Tesseract couldn't tell the image resolution. Small or low-DPI captures OCR poorly; try zooming in before capturing, or adjust --ocr-dpi."
+        );
+    }
+}
+
+/// Runs Tesseract with `psm`, then retries each `psm_fallback` mode in order
+/// while the result is empty.
+fn run_tesseract_with_fallback(image_path: &Path, settings: &OcrSettings) -> Result<String> {
+    let text = run_tesseract(image_path, settings, settings.psm)?;
+    if !text.trim().is_empty() || settings.psm_fallback.is_empty() {
+        return Ok(text);
+    }
+
+    for &psm in &settings.psm_fallback {
+        println!("OCR result was empty. Retrying with --psm {}...", psm);
+        let text = run_tesseract(image_path, settings, Some(psm))?;
+        if !text.trim().is_empty() {
+            println!("PSM {} produced text.", psm);
+            return Ok(text);
+        }
+    }
+    println!("All fallback PSMs produced empty text.");
+    Ok(text)
+}
+
+/// Runs Tesseract in hOCR mode and converts the result to Markdown.
+fn markdown(image_path: &Path, settings: &OcrSettings) -> Result<String> {
+    println!("Running Tesseract CLI (hOCR for Markdown)...");
+    let mut command = tesseract_command(image_path, settings, settings.psm);
+    command.arg("hocr");
+    let hocr_output = run_tesseract_command(command, settings)?;
+    Ok(hocr::to_markdown(&hocr_output))
+}
@@ -0,0 +1,53 @@
+// src/output_file.rs
+// Appending recognized text to the `--output-file` log in a chosen encoding.
+
+use anyhow::{Context, Result};
+use std::{
+    fs::OpenOptions,
+    io::{Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// How text is encoded in the output file.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputEncoding {
+    Utf8,
+    /// UTF-8 with a byte order mark, which Notepad and older tools use to detect UTF-8.
+    Utf8Bom,
+    /// UTF-16 little endian with a byte order mark, the "Unicode" of Windows tools.
+    Utf16le,
+}
+
+impl OutputEncoding {
+    fn bom(self) -> &'static [u8] {
+        match self {
+            OutputEncoding::Utf8 => &[],
+            OutputEncoding::Utf8Bom => &[0xEF, 0xBB, 0xBF],
+            OutputEncoding::Utf16le => &[0xFF, 0xFE],
+        }
+    }
+
+    fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            OutputEncoding::Utf8 | OutputEncoding::Utf8Bom => text.as_bytes().to_vec(),
+            OutputEncoding::Utf16le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+        }
+    }
+}
+
+/// Appends `text` as one entry ending in CRLF, creating the file if needed. The BOM is
+/// written only into an empty file, so appends never repeat it mid-file.
+pub fn append(path: &Path, text: &str, encoding: OutputEncoding) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open output file {:?}", path))?;
+    let mut bytes = Vec::new();
+    if file.seek(SeekFrom::End(0))? == 0 {
+        bytes.extend_from_slice(encoding.bom());
+    }
+    bytes.extend(encoding.encode(&format!("{}\r\n", text)));
+    file.write_all(&bytes)
+        .with_context(|| format!("Failed to write to output file {:?}", path))
+}
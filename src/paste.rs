@@ -0,0 +1,194 @@
+// src/paste.rs
+// Delivering recognized text into the focused application.
+
+use rdev::{simulate, EventType, Key};
+use serde::{Deserialize, Serialize};
+use std::{thread, time::Duration};
+
+/// How recognized text is delivered to the target application.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PasteMethod {
+    /// Place the text on the clipboard, simulate the paste combo, then restore the clipboard.
+    Clipboard,
+    /// Type the text as simulated keystrokes, leaving the clipboard untouched.
+    Type,
+    /// Type the text between the terminal bracketed-paste markers (`ESC [200~` and
+    /// `ESC [201~`), so a shell takes multi-line text as one paste instead of running
+    /// each line as it arrives.
+    Bracketed,
+}
+
+// --- Paste Sequence Variants ---
+/// Orderings of the simulated paste keystrokes. Some apps only register
+/// Ctrl+V when the modifiers arrive in a particular order.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum PasteSequenceVariant {
+    /// Ctrl down, V down, V up, Ctrl up.
+    Standard,
+    /// Ctrl down, V down, Ctrl up, V up.
+    ReleaseCtrlFirst,
+    /// Ctrl up and V up (clearing stuck keys), then the standard sequence.
+    KeyUpFirst,
+    /// Shift down, Insert down, Insert up, Shift up (the legacy paste combo).
+    ShiftInsert,
+}
+
+/// Pause between attempts when a simulated event fails.
+const SIMULATE_RETRY_DELAY: Duration = Duration::from_millis(20);
+
+/// Simulates one event, retrying up to `retries` more times on transient failures.
+fn simulate_with_retry(event: &EventType, retries: u32) -> Result<(), rdev::SimulateError> {
+    let mut attempt = 0;
+    loop {
+        match simulate(event) {
+            Ok(()) => return Ok(()),
+            Err(_) if attempt < retries => {
+                attempt += 1;
+                println!(
+                    "Simulating {:?} failed; retrying ({}/{})...",
+                    event, attempt, retries
+                );
+                thread::sleep(SIMULATE_RETRY_DELAY);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// --- send_ctrl_v (Full Implementation) ---
+pub fn send_ctrl_v(variant: PasteSequenceVariant, retries: u32) -> Result<(), rdev::SimulateError> {
+    let delay = Duration::from_millis(30);
+    let sequence: &[EventType] = match variant {
+        PasteSequenceVariant::Standard => &[
+            EventType::KeyPress(Key::ControlLeft),
+            EventType::KeyPress(Key::KeyV),
+            EventType::KeyRelease(Key::KeyV),
+            EventType::KeyRelease(Key::ControlLeft),
+        ],
+        PasteSequenceVariant::ReleaseCtrlFirst => &[
+            EventType::KeyPress(Key::ControlLeft),
+            EventType::KeyPress(Key::KeyV),
+            EventType::KeyRelease(Key::ControlLeft),
+            EventType::KeyRelease(Key::KeyV),
+        ],
+        PasteSequenceVariant::KeyUpFirst => &[
+            EventType::KeyRelease(Key::ControlLeft),
+            EventType::KeyRelease(Key::KeyV),
+            EventType::KeyPress(Key::ControlLeft),
+            EventType::KeyPress(Key::KeyV),
+            EventType::KeyRelease(Key::KeyV),
+            EventType::KeyRelease(Key::ControlLeft),
+        ],
+        PasteSequenceVariant::ShiftInsert => &[
+            EventType::KeyPress(Key::ShiftLeft),
+            EventType::KeyPress(Key::Insert),
+            EventType::KeyRelease(Key::Insert),
+            EventType::KeyRelease(Key::ShiftLeft),
+        ],
+    };
+    for (i, event) in sequence.iter().enumerate() {
+        if i > 0 {
+            thread::sleep(delay);
+        }
+        simulate_with_retry(event, retries)?;
+    }
+    println!("Paste simulated ({:?}).", variant);
+    Ok(())
+}
+
+/// Presses and releases Enter, moving the target's cursor to a fresh line.
+pub fn press_enter(retries: u32) -> Result<(), rdev::SimulateError> {
+    simulate_with_retry(&EventType::KeyPress(Key::Return), retries)?;
+    thread::sleep(Duration::from_millis(30));
+    simulate_with_retry(&EventType::KeyRelease(Key::Return), retries)?;
+    thread::sleep(Duration::from_millis(30));
+    Ok(())
+}
+
+/// Types the text through `SendInput` with `KEYEVENTF_UNICODE`, which delivers any
+/// Unicode character (accents, CJK, emoji via surrogate pairs) independent of the
+/// keyboard layout. Newlines, tabs and escapes are sent as real Enter/Tab/Esc keys
+/// because many apps ignore them as Unicode input. Pauses `delay` after each
+/// character, so apps that drop fast input can keep up.
+pub fn type_text(text: &str, delay: Duration) -> Result<(), rdev::SimulateError> {
+    use winapi::um::winuser::{VK_ESCAPE, VK_RETURN, VK_TAB};
+
+    for c in text.chars() {
+        match c {
+            '\r' => continue,
+            '\n' => send_virtual_key(VK_RETURN as u16)?,
+            '\t' => send_virtual_key(VK_TAB as u16)?,
+            '\x1b' => send_virtual_key(VK_ESCAPE as u16)?,
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    send_unicode_unit(*unit)?;
+                }
+            }
+        }
+        thread::sleep(delay);
+    }
+    println!(
+        "Typed {} characters.",
+        text.chars().filter(|&c| c != '\r').count()
+    );
+    Ok(())
+}
+
+fn send_inputs(inputs: &mut [winapi::um::winuser::INPUT]) -> Result<(), rdev::SimulateError> {
+    use winapi::um::winuser::{SendInput, INPUT};
+
+    let sent = unsafe {
+        SendInput(
+            inputs.len() as u32,
+            inputs.as_mut_ptr(),
+            std::mem::size_of::<INPUT>() as i32,
+        )
+    };
+    if sent as usize == inputs.len() {
+        Ok(())
+    } else {
+        Err(rdev::SimulateError)
+    }
+}
+
+fn keyboard_input(vk: u16, scan: u16, flags: u32) -> winapi::um::winuser::INPUT {
+    use winapi::um::winuser::{INPUT, INPUT_KEYBOARD, KEYBDINPUT};
+
+    unsafe {
+        let mut input: INPUT = std::mem::zeroed();
+        input.type_ = INPUT_KEYBOARD;
+        *input.u.ki_mut() = KEYBDINPUT {
+            wVk: vk,
+            wScan: scan,
+            dwFlags: flags,
+            time: 0,
+            dwExtraInfo: 0,
+        };
+        input
+    }
+}
+
+fn send_unicode_unit(unit: u16) -> Result<(), rdev::SimulateError> {
+    use winapi::um::winuser::{KEYEVENTF_KEYUP, KEYEVENTF_UNICODE};
+
+    send_inputs(&mut [
+        keyboard_input(0, unit, KEYEVENTF_UNICODE),
+        keyboard_input(0, unit, KEYEVENTF_UNICODE | KEYEVENTF_KEYUP),
+    ])
+}
+
+fn send_virtual_key(vk: u16) -> Result<(), rdev::SimulateError> {
+    use winapi::um::winuser::KEYEVENTF_KEYUP;
+
+    send_inputs(&mut [
+        keyboard_input(vk, 0, 0),
+        keyboard_input(vk, 0, KEYEVENTF_KEYUP),
+    ])
+}
+
+/// Types the text like `type_text`, wrapped in the bracketed-paste markers.
+pub fn type_bracketed(text: &str, delay: Duration) -> Result<(), rdev::SimulateError> {
+    type_text(&format!("\x1b[200~{}\x1b[201~", text), delay)
+}
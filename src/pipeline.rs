@@ -0,0 +1,435 @@
+// src/pipeline.rs
+// Turns an image or audio file into text: the core shared by the binary and embedders.
+
+use crate::{
+    cache::{self, ResultCache},
+    diarize::DiarizeBackend,
+    ocr, tiff_pages,
+    transcribe::trans::{TranscribeOptions, TranscriptFormat},
+};
+use anyhow::{anyhow, Context, Result};
+use async_openai::{config::OpenAIConfig, Client};
+use image::ImageFormat;
+use std::{
+    future::Future,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+use tokio::runtime::Runtime;
+
+pub use crate::ocr::OcrSettings;
+
+/// How often `ProgressCallback::on_tick` fires while a transcription is in flight.
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// One runtime for every transcription in the process.
+static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+
+/// The OpenAI client and the key it was built with, kept so repeated transcriptions
+/// reuse its pooled HTTPS connections instead of paying a fresh TLS handshake each time.
+static OPENAI_CLIENT: Mutex<Option<(String, Client<OpenAIConfig>)>> = Mutex::new(None);
+
+/// Where recognized text is cached between runs.
+#[derive(Debug, Clone)]
+pub struct CacheSettings {
+    pub dir: PathBuf,
+    /// Entries older than this are ignored.
+    pub ttl: Option<Duration>,
+}
+
+/// Everything `process_content` needs.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub ocr: OcrSettings,
+    /// Required for audio; OCR works without it.
+    pub openai_api_key: Option<String>,
+    pub transcribe: TranscribeOptions,
+    /// Transcribe with speaker labels through this service instead of OpenAI.
+    pub diarize: Option<DiarizeBackend>,
+    /// `None` disables the result cache.
+    pub cache: Option<CacheSettings>,
+    /// Log how long each transcription request took.
+    pub timings: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            ocr: OcrSettings::default(),
+            openai_api_key: None,
+            transcribe: TranscribeOptions {
+                model: "whisper-1".to_string(),
+                max_duration_secs: None,
+                min_speech_confidence: None,
+                split_on_silence: None,
+                per_channel: false,
+                temperature: None,
+                format: TranscriptFormat::Text,
+                word_timestamps: false,
+            },
+            diarize: None,
+            cache: None,
+            timings: false,
+        }
+    }
+}
+
+/// Input to recognize.
+#[derive(Debug, Clone)]
+pub enum Content {
+    /// Encoded image bytes in any format the `image` crate reads, e.g. clipboard BMP data.
+    Image(Vec<u8>),
+    ImageFile(PathBuf),
+    /// Audio, or video with an audio track; ffmpeg converts it as needed.
+    AudioFile(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Ocr,
+    Transcription,
+}
+
+#[derive(Debug, Clone)]
+pub struct ProcessOutcome {
+    pub text: String,
+    pub kind: ContentKind,
+    pub elapsed: Duration,
+    /// The text came from the result cache; nothing was recognized.
+    pub cached: bool,
+}
+
+/// Progress events from `process_content_with_progress`. Every method defaults to
+/// doing nothing, so implement only the ones you need.
+pub trait ProgressCallback {
+    fn on_start(&mut self, _kind: ContentKind) {}
+    /// Fired with the prepared image, right before Tesseract reads it.
+    fn on_image_prepared(&mut self, _image_path: &Path) {}
+    /// Fired periodically during long-running work (transcription requests).
+    fn on_tick(&mut self) {}
+    fn on_done(&mut self, _result: &Result<ProcessOutcome>) {}
+}
+
+/// A transcription service. `Config` picks the diarization backend when one is set,
+/// and OpenAI otherwise.
+pub trait Transcriber {
+    /// Names the service in timing logs.
+    fn name(&self) -> &'static str;
+    fn transcribe(
+        &self,
+        audio_path: &Path,
+        options: &TranscribeOptions,
+    ) -> impl Future<Output = Result<String>>;
+}
+
+/// A `ProgressCallback` that ignores every event.
+pub struct NoProgress;
+
+impl ProgressCallback for NoProgress {}
+
+/// Recognizes `content` without progress reporting.
+pub fn process_content(content: Content, config: &Config) -> Result<ProcessOutcome> {
+    process_content_with_progress(content, config, &mut NoProgress)
+}
+
+pub fn process_content_with_progress(
+    content: Content,
+    config: &Config,
+    progress: &mut dyn ProgressCallback,
+) -> Result<ProcessOutcome> {
+    let kind = match content {
+        Content::Image(_) | Content::ImageFile(_) => ContentKind::Ocr,
+        Content::AudioFile(_) => ContentKind::Transcription,
+    };
+    progress.on_start(kind);
+    let started = Instant::now();
+
+    let text = match content {
+        Content::Image(data) => ocr_image(&data, config, progress),
+        Content::ImageFile(path) => ocr_image_file(&path, config, progress),
+        Content::AudioFile(path) => transcribe_file(&path, config, progress),
+    };
+    let result = text.map(|(text, cached)| ProcessOutcome {
+        text,
+        kind,
+        elapsed: started.elapsed(),
+        cached,
+    });
+    progress.on_done(&result);
+    result
+}
+
+fn open_cache(config: &Config) -> Result<Option<ResultCache>> {
+    match &config.cache {
+        Some(settings) => Ok(Some(ResultCache::open(&settings.dir, settings.ttl)?)),
+        None => Ok(None),
+    }
+}
+
+/// Returns the cached text for `key`, or runs `recognize` and caches a non-empty result.
+/// `what` names the result in log messages.
+fn cached_or(
+    cache: Option<&ResultCache>,
+    key: impl FnOnce() -> Result<String>,
+    (what, hit_message): (&str, &str),
+    recognize: impl FnOnce() -> Result<String>,
+) -> Result<(String, bool)> {
+    let Some(cache) = cache else {
+        return recognize().map(|text| (text, false));
+    };
+    let key = key()?;
+    if let Some(text) = cache.get(&key) {
+        println!("{}", hit_message);
+        return Ok((text, true));
+    }
+    let text = recognize()?;
+    if !text.trim().is_empty() {
+        if let Err(e) = cache.put(&key, &text) {
+            eprintln!("Warning: Failed to write {} cache entry: {:?}", what, e);
+        }
+    }
+    Ok((text, false))
+}
+
+/// OCR of one image, served from the result cache when possible, with Tesseract's
+/// form feeds turned into `page_separator`.
+fn ocr_image(
+    image_data: &[u8],
+    config: &Config,
+    progress: &mut dyn ProgressCallback,
+) -> Result<(String, bool)> {
+    let cache = open_cache(config)?;
+    let (text, cached) = cached_or(
+        cache.as_ref(),
+        || Ok(ocr_cache_key(image_data, &config.ocr)),
+        ("OCR", "OCR cache hit. Skipping Tesseract."),
+        || ocr::recognize(image_data, &config.ocr, progress),
+    )?;
+    Ok((separate_pages(&text, &config.ocr), cached))
+}
+
+fn ocr_image_file(
+    path: &Path,
+    config: &Config,
+    progress: &mut dyn ProgressCallback,
+) -> Result<(String, bool)> {
+    let image_data =
+        std::fs::read(path).with_context(|| format!("Failed to read image file {:?}", path))?;
+    let pages = match tiff_pages::split_pages(&image_data) {
+        Ok(pages) => pages,
+        Err(e) => {
+            println!(
+                "Warning: Couldn't split TIFF pages, reading it as one image: {:?}",
+                e
+            );
+            None
+        }
+    };
+    let Some(pages) = pages else {
+        return ocr_image(&image_data, config, progress);
+    };
+
+    println!(
+        "Multi-page TIFF with {} pages. Processing each page separately...",
+        pages.len()
+    );
+    let mut texts = Vec::with_capacity(pages.len());
+    let mut all_cached = true;
+    for (index, page) in pages.iter().enumerate() {
+        println!("OCR page {}/{}...", index + 1, pages.len());
+        let mut bytes = Vec::new();
+        page.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Bmp)?;
+        let (text, cached) = ocr_image(&bytes, config, progress)?;
+        texts.push(text);
+        all_cached &= cached;
+    }
+    Ok((texts.join(&config.ocr.page_separator), all_cached))
+}
+
+/// Drops Tesseract's trailing form feed and turns the remaining ones into `page_separator`.
+fn separate_pages(text: &str, settings: &OcrSettings) -> String {
+    text.trim_end_matches('\x0c')
+        .replace('\x0c', &settings.page_separator)
+}
+
+/// Cache key covering the image bytes and every setting that changes the recognized
+/// text. The Tesseract command, warning logging and page separator are left out: the
+/// same tessdata gives the same text whichever build reads it, and the separator is
+/// applied after the cache.
+fn ocr_cache_key(image_data: &[u8], settings: &OcrSettings) -> String {
+    let psm_settings = format!(
+        "{:?}/{:?}/dpi{}/md{}/rotate{}/mask{:?}/invert{}{}/osd{}/pre{:?}/alpha{:?}",
+        settings.psm,
+        settings.psm_fallback,
+        settings.dpi,
+        settings.markdown,
+        settings.auto_rotate,
+        settings.color_mask,
+        settings.invert,
+        settings.auto_invert,
+        settings.include_osd,
+        settings.pre_command,
+        settings.flatten_alpha
+    );
+    let decoding = format!(
+        "{:?}/lossy{}/partial{}",
+        settings.encoding.map(|encoding| encoding.name()),
+        settings.lossy_utf8,
+        settings.accept_partial
+    );
+    let mut parts: Vec<&[u8]> = vec![
+        b"ocr",
+        image_data,
+        settings.lang.as_bytes(),
+        psm_settings.as_bytes(),
+        decoding.as_bytes(),
+        settings
+            .tessdata_path
+            .as_deref()
+            .unwrap_or_default()
+            .as_bytes(),
+    ];
+    for arg in &settings.extra_args {
+        parts.push(arg.as_bytes());
+    }
+    cache::cache_key(&parts)
+}
+
+/// Cache key covering the audio file contents, the transcription options and the
+/// diarization backend, if any, all of which shape the transcript.
+fn transcription_cache_key(audio_path: &Path, config: &Config) -> Result<String> {
+    let audio_data = std::fs::read(audio_path).with_context(|| {
+        format!(
+            "Failed to read audio file {:?} for the cache key",
+            audio_path
+        )
+    })?;
+    let options = format!("{:?}", config.transcribe);
+    let backend = config
+        .diarize
+        .as_ref()
+        .map_or("", |backend| backend.url.as_str());
+    Ok(cache::cache_key(&[
+        b"transcribe",
+        &audio_data,
+        options.as_bytes(),
+        backend.as_bytes(),
+    ]))
+}
+
+/// Returns the shared client for `api_key`, building it on first use or when the key changes.
+fn openai_client(api_key: &str) -> (Client<OpenAIConfig>, bool) {
+    let mut shared = OPENAI_CLIENT.lock().expect("OpenAI client lock poisoned");
+    match &*shared {
+        Some((key, client)) if key == api_key => (client.clone(), true),
+        _ => {
+            let client = Client::with_config(OpenAIConfig::new().with_api_key(api_key));
+            *shared = Some((api_key.to_string(), client.clone()));
+            (client, false)
+        }
+    }
+}
+
+/// OpenAI's transcription API through the shared client.
+struct OpenAiTranscriber {
+    client: Client<OpenAIConfig>,
+    /// The client, and its pooled connections, came from an earlier request.
+    reused: bool,
+}
+
+impl OpenAiTranscriber {
+    fn new(api_key: &str) -> Self {
+        let (client, reused) = openai_client(api_key);
+        OpenAiTranscriber { client, reused }
+    }
+}
+
+impl Transcriber for OpenAiTranscriber {
+    fn name(&self) -> &'static str {
+        if self.reused {
+            "reused client"
+        } else {
+            "new client"
+        }
+    }
+
+    fn transcribe(
+        &self,
+        audio_path: &Path,
+        options: &TranscribeOptions,
+    ) -> impl Future<Output = Result<String>> {
+        crate::transcribe::trans::transcribe(&self.client, audio_path, options)
+    }
+}
+
+/// Runs `work` on the shared runtime, firing `on_tick` every `TICK_INTERVAL` until it finishes.
+fn block_on_with_ticks(
+    work: impl Future<Output = Result<String>>,
+    progress: &mut dyn ProgressCallback,
+) -> Result<String> {
+    let rt = match RUNTIME.get() {
+        Some(rt) => rt,
+        None => {
+            let rt = Runtime::new().context("Failed to create Tokio runtime")?;
+            RUNTIME.get_or_init(|| rt)
+        }
+    };
+    rt.block_on(async {
+        tokio::pin!(work);
+        let mut ticks = tokio::time::interval(TICK_INTERVAL);
+        ticks.tick().await; // The first tick completes immediately.
+        loop {
+            tokio::select! {
+                result = &mut work => return result,
+                _ = ticks.tick() => progress.on_tick(),
+            }
+        }
+    })
+}
+
+/// Transcribes `path` with `transcriber`, logging the request time with `--timings`.
+fn run_transcriber(
+    transcriber: &impl Transcriber,
+    path: &Path,
+    config: &Config,
+    progress: &mut dyn ProgressCallback,
+) -> Result<String> {
+    let started = Instant::now();
+    let result = block_on_with_ticks(transcriber.transcribe(path, &config.transcribe), progress);
+    if config.timings {
+        println!(
+            "Timing: transcription took {}ms ({}).",
+            started.elapsed().as_millis(),
+            transcriber.name()
+        );
+    }
+    result
+}
+
+fn transcribe_file(
+    path: &Path,
+    config: &Config,
+    progress: &mut dyn ProgressCallback,
+) -> Result<(String, bool)> {
+    let cache = open_cache(config)?;
+    cached_or(
+        cache.as_ref(),
+        || transcription_cache_key(path, config),
+        (
+            "transcription",
+            "Transcription cache hit. Skipping the transcription request.",
+        ),
+        || match &config.diarize {
+            Some(backend) => run_transcriber(backend, path, config, progress),
+            None => {
+                let api_key = config.openai_api_key.as_ref().ok_or_else(|| {
+                    anyhow!("OpenAI API Key is missing (checked arg, .env, env var).")
+                })?;
+                run_transcriber(&OpenAiTranscriber::new(api_key), path, config, progress)
+            }
+        },
+    )
+    .with_context(|| format!("Audio transcription failed for: {:?}", path))
+}
@@ -0,0 +1,106 @@
+// src/record.rs
+// Microphone capture to a temporary WAV file while the trigger key is held.
+
+use anyhow::{anyhow, Context, Result};
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, Sample, SampleFormat};
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Arc, Mutex};
+use tempfile::NamedTempFile;
+
+use crate::tempfiles;
+
+type SharedWriter = Arc<Mutex<Option<hound::WavWriter<BufWriter<File>>>>>;
+
+/// An in-progress recording from the default input device.
+/// Dropping it without calling [`Recording::finish`] discards the audio.
+pub struct Recording {
+    stream: cpal::Stream,
+    writer: SharedWriter,
+    file: NamedTempFile,
+}
+
+impl Recording {
+    /// Starts recording the default microphone into a temporary WAV file.
+    pub fn start() -> Result<Self> {
+        let host = cpal::default_host();
+        let device = host
+            .default_input_device()
+            .ok_or_else(|| anyhow!("No default microphone (input device) found"))?;
+        let config = device
+            .default_input_config()
+            .with_context(|| "Failed to get the microphone's default input config")?;
+
+        let file = tempfiles::file("mic_recording", ".wav")?;
+        let spec = hound::WavSpec {
+            channels: config.channels(),
+            sample_rate: config.sample_rate().0,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let writer = hound::WavWriter::create(file.path(), spec)
+            .with_context(|| format!("Failed to create WAV file {:?}", file.path()))?;
+        let writer: SharedWriter = Arc::new(Mutex::new(Some(writer)));
+
+        let stream_config = config.config();
+        let stream = match config.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &stream_config, writer.clone()),
+            SampleFormat::I16 => build_stream::<i16>(&device, &stream_config, writer.clone()),
+            SampleFormat::U16 => build_stream::<u16>(&device, &stream_config, writer.clone()),
+            other => return Err(anyhow!("Unsupported microphone sample format: {:?}", other)),
+        }?;
+        stream
+            .play()
+            .with_context(|| "Failed to start microphone stream")?;
+
+        Ok(Self {
+            stream,
+            writer,
+            file,
+        })
+    }
+
+    /// Stops recording and returns the finished WAV file.
+    pub fn finish(self) -> Result<NamedTempFile> {
+        drop(self.stream);
+        let writer = self
+            .writer
+            .lock()
+            .map_err(|_| anyhow!("Microphone writer lock poisoned"))?
+            .take();
+        if let Some(writer) = writer {
+            writer
+                .finalize()
+                .with_context(|| "Failed to finalize microphone WAV file")?;
+        }
+        Ok(self.file)
+    }
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    writer: SharedWriter,
+) -> Result<cpal::Stream>
+where
+    T: cpal::SizedSample,
+    i16: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &_| {
+                if let Ok(mut guard) = writer.lock() {
+                    if let Some(writer) = guard.as_mut() {
+                        for &sample in data {
+                            let _ = writer.write_sample(i16::from_sample(sample));
+                        }
+                    }
+                }
+            },
+            |err| eprintln!("Microphone stream error: {}", err),
+            None,
+        )
+        .with_context(|| "Failed to open microphone stream")
+}
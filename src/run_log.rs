@@ -0,0 +1,74 @@
+// src/run_log.rs
+// `--log-file`: one JSON line per run (what ran, how long, whether it worked), rotated by size.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// One finished run. The recognized text itself is never logged, only its length.
+#[derive(Serialize)]
+pub struct RunRecord<'a> {
+    pub timestamp_unix_ms: u128,
+    /// What started the run, e.g. `hotkey` or `watcher`.
+    pub source: &'a str,
+    /// What was processed, e.g. `image` or `audio-file`.
+    pub kind: &'a str,
+    pub result_chars: usize,
+    pub duration_ms: u128,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+impl<'a> RunRecord<'a> {
+    pub fn new(
+        source: &'a str,
+        kind: &'a str,
+        result_chars: usize,
+        duration: Duration,
+        error: Option<&anyhow::Error>,
+    ) -> Self {
+        RunRecord {
+            timestamp_unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+            source,
+            kind,
+            result_chars,
+            duration_ms: duration.as_millis(),
+            success: error.is_none(),
+            error: error.map(|e| format!("{:#}", e)),
+        }
+    }
+}
+
+/// Appends `record` to `path`. When the line would push the file past `max_bytes`,
+/// the file is first renamed to `<path>.1`, replacing the previous backup.
+/// A `max_bytes` of 0 never rotates.
+pub fn append(path: &Path, max_bytes: u64, record: &RunRecord) -> Result<()> {
+    let line = format!("{}\n", serde_json::to_string(record)?);
+    let current_len = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+    if max_bytes > 0 && current_len > 0 && current_len + line.len() as u64 > max_bytes {
+        let backup = backup_path(path);
+        fs::rename(path, &backup)
+            .with_context(|| format!("Failed to rotate log file {:?} to {:?}", path, backup))?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open log file {:?}", path))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to write to log file {:?}", path))
+}
+
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".1");
+    PathBuf::from(backup)
+}
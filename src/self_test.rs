@@ -0,0 +1,140 @@
+// src/self_test.rs
+// `--self-test`: checks sounds, clipboard, input simulation and external tools,
+// then prints a pass/fail checklist suitable for bug reports.
+
+use crate::{
+    check_openai_key, formats, get_clipboard, get_clipboard_content, open_clipboard,
+    restore_clipboard, tone, Args, SoundType,
+};
+use anyhow::{anyhow, Context, Result};
+use clipboard_win::Setter;
+use std::{process::Command, thread, time::Duration};
+use winapi::um::utilapiset::Beep;
+
+const CLIPBOARD_PROBE: &str = "ocrp self-test \u{2713}";
+
+/// Runs every check and prints the checklist. Fails if any check failed.
+pub fn run(args: &Args) -> Result<()> {
+    println!("Running self-test...\n");
+    let mut results: Vec<(String, Result<String>)> = Vec::new();
+
+    if args.no_sound {
+        println!("Skipping sound checks (--no-sound).");
+    } else {
+        for (name, sound) in [
+            ("Start", SoundType::Start),
+            ("Success", SoundType::Success),
+            ("Error", SoundType::Error),
+            ("Waiting", SoundType::Waiting),
+        ] {
+            results.push((format!("Sound: {}", name), check_sound(sound)));
+        }
+    }
+    results.push(("Clipboard round trip".to_string(), check_clipboard(args)));
+    results.push(("Input simulation".to_string(), check_simulate()));
+    results.push((
+        "Tesseract".to_string(),
+        check_tool(args.tesseract_engine().0, "--version"),
+    ));
+    results.push(("ffmpeg".to_string(), check_tool("ffmpeg", "-version")));
+    results.push((
+        "yt-dlp".to_string(),
+        check_tool(&args.pipeline.ytdlp_cmd, "--version"),
+    ));
+    results.push((
+        "OpenAI API key".to_string(),
+        match &args.pipeline.openai_api_key {
+            Some(key) => check_openai_key(key).map(|()| "valid".to_string()),
+            None => Err(anyhow!("not configured")),
+        },
+    ));
+
+    println!("\n--- Self-test results ---");
+    let mut failed = 0;
+    for (name, result) in &results {
+        match result {
+            Ok(detail) => println!("[PASS] {}: {}", name, detail),
+            Err(e) => {
+                failed += 1;
+                println!("[FAIL] {}: {:#}", name, e);
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow!("{} of {} checks failed", failed, results.len()));
+    }
+    println!("All {} checks passed.", results.len());
+    Ok(())
+}
+
+fn check_sound(sound: SoundType) -> Result<String> {
+    let (freq_hz, dur_ms) = tone(&sound);
+    let ok = unsafe { Beep(freq_hz, dur_ms) };
+    thread::sleep(Duration::from_millis(50));
+    if ok == 0 {
+        return Err(anyhow!("Beep failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(format!("{} Hz for {} ms", freq_hz, dur_ms))
+}
+
+/// Writes a probe string, reads it back, then restores the previous clipboard content if any.
+fn check_clipboard(args: &Args) -> Result<String> {
+    let previous_content = get_clipboard_content(args).ok();
+    {
+        let _clip = open_clipboard(args, "run the self-test")?;
+        formats::Unicode
+            .write_clipboard(&CLIPBOARD_PROBE)
+            .map_err(|e| anyhow!("write failed: {}", e))?;
+    }
+    let read_back = get_clipboard::<String, _>(formats::Unicode)
+        .map_err(|e| anyhow!("read back failed: {}", e));
+    if let Some(content) = previous_content {
+        restore_clipboard(content, args).context("failed to restore previous content")?;
+    }
+    match read_back? {
+        text if text == CLIPBOARD_PROBE => Ok("wrote and read back a test string".to_string()),
+        text => Err(anyhow!(
+            "read back {:?}, expected {:?}",
+            text,
+            CLIPBOARD_PROBE
+        )),
+    }
+}
+
+/// Taps Shift, which types nothing in any focused control.
+fn check_simulate() -> Result<String> {
+    for event in [
+        rdev::EventType::KeyPress(rdev::Key::ShiftLeft),
+        rdev::EventType::KeyRelease(rdev::Key::ShiftLeft),
+    ] {
+        rdev::simulate(&event).map_err(|_| anyhow!("could not simulate {:?}", event))?;
+        thread::sleep(Duration::from_millis(30));
+    }
+    Ok("tapped Shift".to_string())
+}
+
+fn check_tool(cmd: &str, version_arg: &str) -> Result<String> {
+    let output = Command::new(cmd)
+        .arg(version_arg)
+        .output()
+        .with_context(|| format!("'{}' could not be run (is it installed and in PATH?)", cmd))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "'{} {}' exited with {}",
+            cmd,
+            version_arg,
+            output.status
+        ));
+    }
+    // Tesseract prints its version to stderr on some builds.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first_line = stdout
+        .lines()
+        .chain(stderr.lines())
+        .find(|line| !line.trim().is_empty())
+        .unwrap_or("ok")
+        .trim()
+        .to_string();
+    Ok(first_line)
+}
@@ -0,0 +1,266 @@
+// src/settings_gui.rs
+// Small native settings window (`--settings`) that edits the config file.
+
+use crate::{config, config::Config, easy_rdev_key::PTTKey, Args};
+use anyhow::{anyhow, Context, Result};
+use clap::ValueEnum;
+use eframe::egui;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// Opens the settings window. Returns `true` if the user chose "Save & Start",
+/// meaning the listener should start with the saved settings.
+pub fn run(args: &Args, config_path: &Path) -> Result<bool> {
+    let base = if config_path.exists() {
+        config::load(config_path)?
+    } else {
+        Config::default()
+    };
+    let start_listener = Arc::new(AtomicBool::new(false));
+    let app = SettingsApp {
+        config_path: config_path.to_path_buf(),
+        trigger_key: args.trigger_key,
+        capturing_key: false,
+        lang: args.pipeline.lang.clone(),
+        psm: args
+            .pipeline
+            .psm
+            .map(|psm| psm.to_string())
+            .unwrap_or_default(),
+        transcribe_model: args.pipeline.transcribe_model.clone(),
+        beeps: args.beeps,
+        paste_delay_ms: args.paste_delay_ms,
+        base,
+        status: String::new(),
+        start_listener: Arc::clone(&start_listener),
+    };
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([380.0, 300.0])
+            .with_resizable(false),
+        ..Default::default()
+    };
+    eframe::run_native("OCRP Settings", options, Box::new(|_cc| Ok(Box::new(app))))
+        .map_err(|e| anyhow!("Settings window failed: {}", e))?;
+
+    Ok(start_listener.load(Ordering::SeqCst))
+}
+
+struct SettingsApp {
+    config_path: PathBuf,
+    trigger_key: Option<PTTKey>,
+    capturing_key: bool,
+    lang: String,
+    psm: String,
+    transcribe_model: String,
+    beeps: bool,
+    paste_delay_ms: u64,
+    /// The file as loaded, so sections this window doesn't edit are preserved.
+    base: Config,
+    status: String,
+    start_listener: Arc<AtomicBool>,
+}
+
+impl SettingsApp {
+    fn validated_config(&self) -> Result<Config, String> {
+        let trigger_key = self.trigger_key.ok_or("Pick a trigger key.")?;
+        let lang_is_valid = !self.lang.is_empty()
+            && self.lang.split('+').all(|code| {
+                !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            });
+        if !lang_is_valid {
+            return Err("Language must look like 'eng' or 'eng+deu'.".to_string());
+        }
+        let psm = match self.psm.trim() {
+            "" => None,
+            text => match text.parse::<u32>() {
+                Ok(psm) if psm <= 13 => Some(psm),
+                _ => return Err("PSM must be a number from 0 to 13, or empty.".to_string()),
+            },
+        };
+        if self.transcribe_model.trim().is_empty() {
+            return Err("Transcription model can't be empty.".to_string());
+        }
+
+        let mut config = self.base.clone();
+        config.trigger_key = Some(trigger_key);
+        config.lang = Some(self.lang.clone());
+        config.psm = psm;
+        config.beeps = Some(self.beeps);
+        config.paste_delay_ms = Some(self.paste_delay_ms);
+        config.transcribe_model = Some(self.transcribe_model.trim().to_string());
+        Ok(config)
+    }
+
+    fn save(&mut self) -> bool {
+        let config = match self.validated_config() {
+            Ok(config) => config,
+            Err(message) => {
+                self.status = message;
+                return false;
+            }
+        };
+        match write_config(&self.config_path, &config) {
+            Ok(()) => {
+                self.status = format!("Saved to {}", self.config_path.display());
+                println!("Settings saved to {:?}", self.config_path);
+                true
+            }
+            Err(e) => {
+                self.status = format!("Save failed: {:#}", e);
+                false
+            }
+        }
+    }
+}
+
+fn write_config(path: &Path, config: &Config) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create config directory {:?}", parent))?;
+    }
+    let contents = toml::to_string_pretty(config).context("Failed to serialize settings")?;
+    fs::write(path, contents).with_context(|| format!("Failed to write config file {:?}", path))
+}
+
+/// Maps a key pressed in the window to the matching trigger key, if there is one.
+fn ptt_key_from_egui(key: egui::Key) -> Option<PTTKey> {
+    use egui::Key as E;
+    let name = match key {
+        E::ArrowDown => "down-arrow".to_string(),
+        E::ArrowLeft => "left-arrow".to_string(),
+        E::ArrowRight => "right-arrow".to_string(),
+        E::ArrowUp => "up-arrow".to_string(),
+        E::Enter => "return".to_string(),
+        E::Backtick => "back-quote".to_string(),
+        E::Backslash => "back-slash".to_string(),
+        E::OpenBracket => "left-bracket".to_string(),
+        E::CloseBracket => "right-bracket".to_string(),
+        E::Semicolon => "semi-colon".to_string(),
+        E::Period => "dot".to_string(),
+        E::Equals => "equal".to_string(),
+        _ => {
+            let name = key.name();
+            if name.len() == 1 && name.chars().all(|c| c.is_ascii_alphabetic()) {
+                format!("key-{}", name.to_ascii_lowercase())
+            } else if name.len() == 1 && name.chars().all(|c| c.is_ascii_digit()) {
+                format!("num{}", name)
+            } else {
+                // "PageDown" -> "page-down", matching the CLI value names.
+                let mut kebab = String::new();
+                for (i, c) in name.chars().enumerate() {
+                    if i > 0 && c.is_ascii_uppercase() {
+                        kebab.push('-');
+                    }
+                    kebab.push(c.to_ascii_lowercase());
+                }
+                kebab
+            }
+        }
+    };
+    PTTKey::from_str(&name, true).ok()
+}
+
+impl eframe::App for SettingsApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        if self.capturing_key {
+            let pressed = ctx.input(|input| {
+                input.events.iter().find_map(|event| match event {
+                    egui::Event::Key {
+                        key, pressed: true, ..
+                    } => Some(*key),
+                    _ => None,
+                })
+            });
+            if let Some(key) = pressed {
+                match ptt_key_from_egui(key) {
+                    Some(ptt_key) => {
+                        self.trigger_key = Some(ptt_key);
+                        self.status.clear();
+                    }
+                    None => self.status = format!("{} can't be used as a trigger key.", key.name()),
+                }
+                self.capturing_key = false;
+            }
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::Grid::new("settings")
+                .num_columns(2)
+                .spacing([12.0, 8.0])
+                .show(ui, |ui| {
+                    ui.label("Trigger key");
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_salt("trigger_key")
+                            .selected_text(
+                                self.trigger_key
+                                    .map(|key| format!("{:?}", key))
+                                    .unwrap_or_else(|| "(none)".to_string()),
+                            )
+                            .show_ui(ui, |ui| {
+                                for key in PTTKey::value_variants() {
+                                    ui.selectable_value(
+                                        &mut self.trigger_key,
+                                        Some(*key),
+                                        format!("{:?}", key),
+                                    );
+                                }
+                            });
+                        let label = if self.capturing_key {
+                            "Press a key…"
+                        } else {
+                            "Capture"
+                        };
+                        if ui.button(label).clicked() {
+                            self.capturing_key = !self.capturing_key;
+                        }
+                    });
+                    ui.end_row();
+
+                    ui.label("Language");
+                    ui.text_edit_singleline(&mut self.lang);
+                    ui.end_row();
+
+                    ui.label("PSM");
+                    ui.text_edit_singleline(&mut self.psm);
+                    ui.end_row();
+
+                    ui.label("Transcription model");
+                    ui.text_edit_singleline(&mut self.transcribe_model);
+                    ui.end_row();
+
+                    ui.label("Paste delay (ms)");
+                    ui.add(egui::DragValue::new(&mut self.paste_delay_ms).range(0..=2000));
+                    ui.end_row();
+
+                    ui.label("Beeps");
+                    ui.checkbox(&mut self.beeps, "Start/success beeps");
+                    ui.end_row();
+                });
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    self.save();
+                }
+                if ui.button("Save & Start").clicked() && self.save() {
+                    self.start_listener.store(true, Ordering::SeqCst);
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+                if ui.button("Close").clicked() {
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                }
+            });
+            if !self.status.is_empty() {
+                ui.label(&self.status);
+            }
+        });
+    }
+}
@@ -0,0 +1,92 @@
+// src/simulate_clipboard.rs
+// `--simulate-clipboard` (hidden): runs the pipeline on synthetic clipboard content and
+// reports what would be pasted and restored. Never reads or writes the real clipboard.
+
+use crate::{
+    finalize_text, paste::PasteMethod, recognize_clipboard_content, resolve_paste_method, Args,
+    ClipboardContent,
+};
+use anyhow::{anyhow, Context, Result};
+use image::ImageFormat;
+use std::io::Cursor;
+
+pub fn run(spec: &str, args: &Args) -> Result<()> {
+    let content = parse_spec(spec)?;
+    println!("== Simulated clipboard ==");
+    println!("Content: {}", content.describe());
+
+    println!("\n== Pipeline ==");
+    let result = recognize_clipboard_content(&content, args);
+
+    println!("\n== Outcome ==");
+    match result {
+        Ok(text) if text.trim().is_empty() => {
+            println!("Empty result: nothing would be pasted.");
+            println!("Would restore immediately: {}", content.describe());
+        }
+        Ok(text) => {
+            let output_text = finalize_text(&text, args);
+            match resolve_paste_method(args) {
+                PasteMethod::Clipboard => {
+                    println!(
+                        "Would place {} chars on the clipboard and press Ctrl+V:",
+                        output_text.chars().count()
+                    );
+                    println!("{}", output_text);
+                    println!(
+                        "Would restore after {}ms: {}",
+                        args.paste_delay_ms,
+                        content.describe()
+                    );
+                }
+                PasteMethod::Type | PasteMethod::Bracketed => {
+                    println!("Would type {} chars:", output_text.chars().count());
+                    println!("{}", output_text);
+                    println!("Would restore nothing: typing leaves the clipboard untouched.");
+                }
+            }
+        }
+        Err(e) => {
+            println!("Error: {:?}", e);
+            println!("Would restore immediately: {}", content.describe());
+        }
+    }
+    if !args.force_restore {
+        println!("(A restore is skipped if something else changes the clipboard first; see --force-restore.)");
+    }
+    Ok(())
+}
+
+/// `png:<image file>` (any format the `image` crate reads, held as a BMP like a real
+/// clipboard bitmap), `filelist:<path>[;<path>...]` or `text:<text>`.
+fn parse_spec(spec: &str) -> Result<ClipboardContent> {
+    let (kind, value) = spec.split_once(':').ok_or_else(|| {
+        anyhow!(
+            "--simulate-clipboard expects 'png:<file>', 'filelist:<paths>' or 'text:<text>', got '{}'",
+            spec
+        )
+    })?;
+    match kind {
+        "png" => {
+            let data = std::fs::read(value)
+                .with_context(|| format!("Failed to read image file {:?}", value))?;
+            let img = image::load_from_memory(&data)
+                .with_context(|| format!("Failed to decode image file {:?}", value))?;
+            let mut bitmap = Vec::new();
+            img.write_to(&mut Cursor::new(&mut bitmap), ImageFormat::Bmp)?;
+            Ok(ClipboardContent::Bitmap(bitmap))
+        }
+        "filelist" => Ok(ClipboardContent::FileList(
+            value
+                .split(';')
+                .filter(|path| !path.is_empty())
+                .map(str::to_string)
+                .collect(),
+        )),
+        "text" => Ok(ClipboardContent::Text(value.to_string())),
+        _ => Err(anyhow!(
+            "Unknown --simulate-clipboard kind '{}'. Use png, filelist or text.",
+            kind
+        )),
+    }
+}
@@ -0,0 +1,166 @@
+// src/tempfiles.rs
+// Creation and cleanup of the temporary files and directories OCRP writes.
+// Everything lives in its own `ocrp` subdirectory of the system temp directory, so
+// the stale sweep never touches files other applications put there.
+
+use anyhow::{Context, Result};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        OnceLock,
+    },
+    time::{Duration, SystemTime},
+};
+use tempfile::{Builder as TempFileBuilder, NamedTempFile, TempDir};
+
+pub const DEFAULT_PREFIX: &str = "ocrp_";
+/// Subdirectory of the system temp directory that holds every OCRP temp file.
+const TEMP_SUBDIR: &str = "ocrp";
+
+static PREFIX: OnceLock<String> = OnceLock::new();
+
+/// Sets the prefix for every temp file created afterwards. Only the first call has an effect.
+pub fn set_prefix(prefix: &str) {
+    let _ = PREFIX.set(prefix.to_string());
+}
+
+fn prefix() -> &'static str {
+    PREFIX.get().map_or(DEFAULT_PREFIX, String::as_str)
+}
+
+/// The directory OCRP owns inside the system temp directory, without creating it.
+fn base_dir() -> PathBuf {
+    std::env::temp_dir().join(TEMP_SUBDIR)
+}
+
+/// Returns [`base_dir`], creating it if needed.
+fn ensure_base_dir() -> Result<PathBuf> {
+    let dir = base_dir();
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create temp directory {:?}", dir))?;
+    Ok(dir)
+}
+
+/// Free space to keep in the temp directory, in bytes; 0 disables the check.
+static MIN_FREE_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Sets the free space every temp file or directory creation requires.
+pub fn set_min_free_mb(min_free_mb: u64) {
+    MIN_FREE_BYTES.store(min_free_mb * 1024 * 1024, Ordering::Relaxed);
+}
+
+/// Fails with a clear message unless the temp directory's drive has `needed_bytes`
+/// free on top of the minimum. Passes when free space can't be determined.
+pub fn ensure_free_space(needed_bytes: u64) -> Result<()> {
+    let min_free = MIN_FREE_BYTES.load(Ordering::Relaxed);
+    if min_free == 0 {
+        return Ok(());
+    }
+    let temp_dir = std::env::temp_dir();
+    let Some(free) = free_bytes(&temp_dir) else {
+        return Ok(());
+    };
+    let required = min_free.saturating_add(needed_bytes);
+    if free < required {
+        anyhow::bail!(
+            "Not enough free disk space in {:?}: {} MB free, {} MB needed ({} MB for this step plus --min-free-mb {}).",
+            temp_dir,
+            free / (1024 * 1024),
+            required.div_ceil(1024 * 1024),
+            needed_bytes.div_ceil(1024 * 1024),
+            min_free / (1024 * 1024)
+        );
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn free_bytes(dir: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use winapi::{shared::ntdef::ULARGE_INTEGER, um::fileapi::GetDiskFreeSpaceExW};
+
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut free: ULARGE_INTEGER = unsafe { std::mem::zeroed() };
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut free,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return None;
+    }
+    Some(unsafe { *free.QuadPart() })
+}
+
+#[cfg(not(windows))]
+fn free_bytes(_dir: &Path) -> Option<u64> {
+    None
+}
+
+fn builder_prefix(purpose: &str) -> String {
+    format!("{}{}_", prefix(), purpose)
+}
+
+/// Creates a temp file named `<prefix><purpose>_XXXX<suffix>`, removed when dropped.
+pub fn file(purpose: &str, suffix: &str) -> Result<NamedTempFile> {
+    ensure_free_space(0)?;
+    TempFileBuilder::new()
+        .prefix(&builder_prefix(purpose))
+        .suffix(suffix)
+        .tempfile_in(ensure_base_dir()?)
+        .with_context(|| format!("Failed to create temporary file for {}", purpose))
+}
+
+/// Creates a temp directory named `<prefix><purpose>_XXXX`, removed with its contents when dropped.
+pub fn dir(purpose: &str) -> Result<TempDir> {
+    ensure_free_space(0)?;
+    TempFileBuilder::new()
+        .prefix(&builder_prefix(purpose))
+        .tempdir_in(ensure_base_dir()?)
+        .with_context(|| format!("Failed to create temporary directory for {}", purpose))
+}
+
+/// Removes temp files and directories older than `max_age` from OCRP's own temp
+/// directory, left behind by runs that exited before their guards dropped. Returns
+/// how many entries were removed. Nothing outside that directory is touched,
+/// whatever `--temp-prefix` is set to.
+pub fn sweep_stale(max_age: Duration) -> usize {
+    let Ok(entries) = fs::read_dir(base_dir()) else {
+        return 0;
+    };
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let is_stale = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= max_age);
+        if !is_stale {
+            continue;
+        }
+        let path = entry.path();
+        let result = if metadata.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        match result {
+            Ok(()) => removed += 1,
+            Err(e) => eprintln!(
+                "Warning: Failed to remove stale temp file {:?}: {}",
+                path, e
+            ),
+        }
+    }
+    removed
+}
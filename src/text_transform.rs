@@ -0,0 +1,331 @@
+// src/text_transform.rs
+// Pure transformations applied to recognized text before it is pasted.
+
+use regex::Regex;
+
+/// Letter case applied to the recognized text.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextCase {
+    /// Keep the text as recognized.
+    None,
+    Lower,
+    Upper,
+    /// Capitalize the first letter of each word and lowercase the rest.
+    Title,
+}
+
+pub fn apply_case(text: &str, case: TextCase) -> String {
+    match case {
+        TextCase::None => text.to_string(),
+        TextCase::Lower => text.to_lowercase(),
+        TextCase::Upper => text.to_uppercase(),
+        TextCase::Title => to_title_case(text),
+    }
+}
+
+/// How much surrounding whitespace is removed from recognized text.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrimMode {
+    /// Remove all leading and trailing whitespace.
+    Full,
+    /// Remove only blank leading and trailing lines, keeping the first line's indentation.
+    Lines,
+    /// Keep the text verbatim.
+    None,
+}
+
+pub fn apply_trim(text: &str, mode: TrimMode) -> &str {
+    match mode {
+        TrimMode::Full => text.trim(),
+        TrimMode::Lines => trim_blank_lines(text),
+        TrimMode::None => text,
+    }
+}
+
+/// Cuts from the start of the first non-blank line to the end of the last one.
+fn trim_blank_lines(text: &str) -> &str {
+    let Some(first_char) = text.find(|c: char| !c.is_whitespace()) else {
+        return "";
+    };
+    let start = text[..first_char]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    &text[start..text.trim_end().len()]
+}
+
+/// A word starts at a letter or digit that follows anything other than a letter,
+/// digit or in-word apostrophe, so "don't stop" becomes "Don't Stop".
+fn to_title_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut in_word = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if in_word {
+                result.extend(c.to_lowercase());
+            } else {
+                result.extend(c.to_uppercase());
+            }
+            in_word = true;
+        } else {
+            result.push(c);
+            in_word = in_word && (c == '\'' || c == '\u{2019}');
+        }
+    }
+    result
+}
+
+/// Every character `--ascii-normalize` rewrites, with its replacement. Anything not
+/// listed is kept as is.
+pub const ASCII_NORMALIZATIONS: &[(char, &str)] = &[
+    ('\u{2018}', "'"),   // ‘ left single quote
+    ('\u{2019}', "'"),   // ’ right single quote, apostrophe
+    ('\u{201A}', "'"),   // ‚ low single quote
+    ('\u{2032}', "'"),   // ′ prime
+    ('\u{201C}', "\""),  // “ left double quote
+    ('\u{201D}', "\""),  // ” right double quote
+    ('\u{201E}', "\""),  // „ low double quote
+    ('\u{2033}', "\""),  // ″ double prime
+    ('\u{00AB}', "\""),  // « left guillemet
+    ('\u{00BB}', "\""),  // » right guillemet
+    ('\u{2010}', "-"),   // ‐ hyphen
+    ('\u{2011}', "-"),   // ‑ non-breaking hyphen
+    ('\u{2012}', "-"),   // ‒ figure dash
+    ('\u{2013}', "-"),   // – en dash
+    ('\u{2014}', "--"),  // — em dash
+    ('\u{2212}', "-"),   // − minus sign
+    ('\u{2026}', "..."), // … ellipsis
+    ('\u{2022}', "*"),   // • bullet
+    ('\u{00A0}', " "),   // no-break space
+    ('\u{2009}', " "),   // thin space
+    ('\u{202F}', " "),   // narrow no-break space
+    ('\u{00AD}', ""),    // soft hyphen
+    ('\u{200B}', ""),    // zero-width space
+    ('\u{FB00}', "ff"),  // ﬀ
+    ('\u{FB01}', "fi"),  // ﬁ
+    ('\u{FB02}', "fl"),  // ﬂ
+    ('\u{FB03}', "ffi"), // ﬃ
+    ('\u{FB04}', "ffl"), // ﬄ
+    ('\u{FB05}', "st"),  // ﬅ
+    ('\u{FB06}', "st"),  // ﬆ
+];
+
+/// Replaces typographic quotes, dashes, spaces and ligatures per [`ASCII_NORMALIZATIONS`].
+pub fn ascii_normalize(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match ASCII_NORMALIZATIONS.iter().find(|(from, _)| *from == c) {
+            Some((_, to)) => result.push_str(to),
+            None => result.push(c),
+        }
+    }
+    result
+}
+
+/// Replaces each line break (`\r\n`, `\n` or `\r`) with the two characters `\n`, so
+/// multi-line text lands as one line in fields that submit on Enter.
+pub fn escape_newlines(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', "\\n")
+}
+
+/// One `--replace '<regex>=><replacement>'` rule. The replacement may reference
+/// capture groups as `$1` or `${name}`.
+#[derive(Clone, Debug)]
+pub struct ReplaceRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+/// Parses `<regex>=><replacement>`, splitting on the first `=>`.
+pub fn parse_replace_rule(spec: &str) -> Result<ReplaceRule, String> {
+    let (pattern, replacement) = spec
+        .split_once("=>")
+        .ok_or_else(|| format!("expected '<regex>=><replacement>', got '{}'", spec))?;
+    let pattern = Regex::new(pattern).map_err(|e| format!("invalid regex '{}': {}", pattern, e))?;
+    Ok(ReplaceRule {
+        pattern,
+        replacement: replacement.to_string(),
+    })
+}
+
+/// Applies each rule in order, every match replaced.
+pub fn apply_replacements(text: &str, rules: &[ReplaceRule]) -> String {
+    let mut result = text.to_string();
+    for rule in rules {
+        result = rule
+            .pattern
+            .replace_all(&result, rule.replacement.as_str())
+            .into_owned();
+    }
+    result
+}
+
+/// Parses a separator given on the command line, expanding `\n`, `\r`, `\t` and `\\`.
+pub fn parse_escaped(spec: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(spec.len());
+    let mut chars = spec.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('t') => result.push('\t'),
+            Some('\\') => result.push('\\'),
+            Some(other) => return Err(format!("unknown escape '\\{}' in '{}'", other, spec)),
+            None => return Err(format!("trailing '\\' in '{}'", spec)),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lower_and_upper_case() {
+        assert_eq!(
+            apply_case("Hello World 42", TextCase::Lower),
+            "hello world 42"
+        );
+        assert_eq!(
+            apply_case("Hello World 42", TextCase::Upper),
+            "HELLO WORLD 42"
+        );
+        assert_eq!(apply_case("Hello World", TextCase::None), "Hello World");
+    }
+
+    #[test]
+    fn title_case_keeps_apostrophes_inside_words() {
+        assert_eq!(apply_case("don't stop", TextCase::Title), "Don't Stop");
+        assert_eq!(
+            apply_case("DON\u{2019}T STOP", TextCase::Title),
+            "Don\u{2019}t Stop"
+        );
+        assert_eq!(
+            apply_case("'quoted' words", TextCase::Title),
+            "'Quoted' Words"
+        );
+    }
+
+    #[test]
+    fn title_case_with_digits() {
+        assert_eq!(
+            apply_case("3RD place on route 66", TextCase::Title),
+            "3rd Place On Route 66"
+        );
+        assert_eq!(apply_case("a-b c.d", TextCase::Title), "A-B C.D");
+    }
+
+    fn rules(specs: &[&str]) -> Vec<ReplaceRule> {
+        specs
+            .iter()
+            .map(|spec| parse_replace_rule(spec).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn replacements_apply_in_order() {
+        let text = "cat dog";
+        assert_eq!(
+            apply_replacements(text, &rules(&["cat=>dog", "dog=>bird"])),
+            "bird bird"
+        );
+        assert_eq!(
+            apply_replacements(text, &rules(&["dog=>bird", "cat=>dog"])),
+            "dog bird"
+        );
+    }
+
+    #[test]
+    fn replacements_expand_captures() {
+        let numbered = rules(&[r"(\w+)@(\w+)=>$2 at $1"]);
+        assert_eq!(apply_replacements("me@home", &numbered), "home at me");
+        let named = rules(&[r"(?P<day>\d{2})/(?P<month>\d{2})=>${month}-${day}"]);
+        assert_eq!(apply_replacements("on 31/12", &named), "on 12-31");
+    }
+
+    #[test]
+    fn replace_rule_splits_on_first_arrow() {
+        let rule = rules(&["a=>b=>c"]);
+        assert_eq!(apply_replacements("a", &rule), "b=>c");
+    }
+
+    #[test]
+    fn replace_rule_errors() {
+        let missing = parse_replace_rule("no arrow here").unwrap_err();
+        assert!(missing.contains("expected"), "{}", missing);
+        let invalid = parse_replace_rule("(unclosed=>x").unwrap_err();
+        assert!(invalid.contains("invalid regex"), "{}", invalid);
+    }
+
+    #[test]
+    fn trim_lines_removes_blank_lines_and_keeps_indentation() {
+        let text = "\n  \n    indented\n  body\n\n \n";
+        assert_eq!(apply_trim(text, TrimMode::Lines), "    indented\n  body");
+        assert_eq!(apply_trim(text, TrimMode::Full), "indented\n  body");
+        assert_eq!(apply_trim(text, TrimMode::None), text);
+    }
+
+    #[test]
+    fn trim_lines_handles_crlf() {
+        let text = "\r\n \r\n\tfirst\r\nsecond\r\n\r\n";
+        assert_eq!(apply_trim(text, TrimMode::Lines), "\tfirst\r\nsecond");
+    }
+
+    #[test]
+    fn trim_lines_of_whitespace_is_empty() {
+        assert_eq!(apply_trim(" \n\t\r\n ", TrimMode::Lines), "");
+        assert_eq!(apply_trim("", TrimMode::Lines), "");
+    }
+
+    #[test]
+    fn ascii_normalize_quotes_and_dashes() {
+        assert_eq!(
+            ascii_normalize("\u{201C}It\u{2019}s\u{201D} \u{2018}ok\u{2019}"),
+            "\"It's\" 'ok'"
+        );
+        assert_eq!(ascii_normalize("a\u{2014}b c\u{2013}d"), "a--b c-d");
+        assert_eq!(ascii_normalize("wait\u{2026}"), "wait...");
+    }
+
+    #[test]
+    fn ascii_normalize_spaces_and_invisible_characters() {
+        assert_eq!(ascii_normalize("10\u{00A0}kg 5\u{2009}m"), "10 kg 5 m");
+        assert_eq!(
+            ascii_normalize("hy\u{00AD}phen zero\u{200B}width"),
+            "hyphen zerowidth"
+        );
+    }
+
+    #[test]
+    fn ascii_normalize_ligatures() {
+        assert_eq!(
+            ascii_normalize("\u{FB01}ne \u{FB02}ow o\u{FB00} o\u{FB03}ce ba\u{FB04}e"),
+            "fine flow off office baffle"
+        );
+    }
+
+    #[test]
+    fn ascii_normalize_keeps_plain_ascii() {
+        let text = "Plain \"ASCII\" text - it's fine... 100% (ok)\n";
+        assert_eq!(ascii_normalize(text), text);
+    }
+
+    #[test]
+    fn escape_newlines_each_line_ending() {
+        assert_eq!(escape_newlines("a\nb"), "a\\nb");
+        assert_eq!(escape_newlines("a\r\nb"), "a\\nb");
+        assert_eq!(escape_newlines("a\rb"), "a\\nb");
+    }
+
+    #[test]
+    fn escape_newlines_mixed_input() {
+        assert_eq!(escape_newlines("a\r\nb\nc\rd\n\n"), "a\\nb\\nc\\nd\\n\\n");
+        assert_eq!(escape_newlines("no breaks"), "no breaks");
+    }
+}
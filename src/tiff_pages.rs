@@ -0,0 +1,91 @@
+// src/tiff_pages.rs
+// Splits multi-page TIFF files into separate images, so each page is prepared on its own.
+
+use anyhow::{anyhow, Context, Result};
+use image::{DynamicImage, GrayImage, ImageBuffer, RgbImage, RgbaImage};
+use std::io::Cursor;
+use tiff::{
+    decoder::{Decoder, DecodingResult},
+    tags::{PhotometricInterpretation, Tag},
+    ColorType,
+};
+
+/// Returns every page of a multi-page TIFF, or `None` for other data and single
+/// pages, which the `image` crate decodes directly.
+pub fn split_pages(data: &[u8]) -> Result<Option<Vec<DynamicImage>>> {
+    let is_tiff = data.starts_with(b"II*\0") || data.starts_with(b"MM\0*");
+    if !is_tiff {
+        return Ok(None);
+    }
+    let mut decoder = Decoder::new(Cursor::new(data)).context("Failed to read TIFF")?;
+    if !decoder.more_images() {
+        return Ok(None);
+    }
+
+    let mut pages = vec![decode_page(&mut decoder, 1)?];
+    while decoder.more_images() {
+        decoder
+            .next_image()
+            .context("Failed to read the next TIFF page")?;
+        pages.push(decode_page(&mut decoder, pages.len() + 1)?);
+    }
+    Ok(Some(pages))
+}
+
+fn decode_page(decoder: &mut Decoder<Cursor<&[u8]>>, page: usize) -> Result<DynamicImage> {
+    let (width, height) = decoder.dimensions()?;
+    let color_type = decoder.colortype()?;
+    let white_is_zero = decoder
+        .get_tag_u32(Tag::PhotometricInterpretation)
+        .is_ok_and(|value| value == PhotometricInterpretation::WhiteIsZero.to_u16() as u32);
+    let pixels = decoder
+        .read_image()
+        .with_context(|| format!("Failed to decode TIFF page {}", page))?;
+
+    let unsupported = || {
+        anyhow!(
+            "TIFF page {} has an unsupported color type {:?}",
+            page,
+            color_type
+        )
+    };
+    let malformed = || anyhow!("TIFF page {} has the wrong amount of pixel data", page);
+    let mut image = match (color_type, pixels) {
+        (ColorType::Gray(1), DecodingResult::U8(bits)) => DynamicImage::ImageLuma8(
+            GrayImage::from_raw(width, height, expand_bits(&bits, width)).ok_or_else(malformed)?,
+        ),
+        (ColorType::Gray(8), DecodingResult::U8(data)) => DynamicImage::ImageLuma8(
+            GrayImage::from_raw(width, height, data).ok_or_else(malformed)?,
+        ),
+        (ColorType::Gray(16), DecodingResult::U16(data)) => DynamicImage::ImageLuma16(
+            ImageBuffer::from_raw(width, height, data).ok_or_else(malformed)?,
+        ),
+        (ColorType::RGB(8), DecodingResult::U8(data)) => {
+            DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, data).ok_or_else(malformed)?)
+        }
+        (ColorType::RGBA(8), DecodingResult::U8(data)) => DynamicImage::ImageRgba8(
+            RgbaImage::from_raw(width, height, data).ok_or_else(malformed)?,
+        ),
+        _ => return Err(unsupported()),
+    };
+    if white_is_zero && matches!(color_type, ColorType::Gray(_)) {
+        image.invert();
+    }
+    Ok(image)
+}
+
+/// Unpacks 1-bit rows (each padded to a whole byte) into 0/255 gray pixels.
+fn expand_bits(bits: &[u8], width: u32) -> Vec<u8> {
+    let row_bytes = (width as usize).div_ceil(8);
+    bits.chunks(row_bytes)
+        .flat_map(|row| {
+            (0..width as usize).map(move |x| {
+                if row[x / 8] & (0x80 >> (x % 8)) != 0 {
+                    255
+                } else {
+                    0
+                }
+            })
+        })
+        .collect()
+}
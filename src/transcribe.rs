@@ -2,20 +2,86 @@
 pub mod trans {
 
     use anyhow::{anyhow, bail, Context, Result};
-    use async_openai::{config::OpenAIConfig, types::CreateTranscriptionRequestArgs, Client};
+    use async_openai::{
+        config::OpenAIConfig,
+        types::{
+            AudioResponseFormat, CreateTranscriptionRequestArgs, TimestampGranularity,
+            TranscriptionSegment, TranscriptionWord,
+        },
+        Client,
+    };
+    use serde::{Deserialize, Serialize};
     use std::{
         path::{Path, PathBuf},
         process::Command,
     };
-    use tempfile::tempdir;
+
+    /// What the transcription API returns.
+    #[derive(
+        clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize,
+    )]
+    #[serde(rename_all = "lowercase")]
+    pub enum TranscriptFormat {
+        /// Plain text.
+        #[default]
+        Text,
+        /// SubRip subtitles with timestamps.
+        Srt,
+        /// WebVTT subtitles with timestamps.
+        Vtt,
+    }
+
+    /// Settings that shape a transcription request.
+    #[derive(Debug, Clone)]
+    pub struct TranscribeOptions {
+        /// OpenAI transcription model, e.g. `whisper-1`.
+        pub model: String,
+        /// Only transcribe the first N seconds of audio.
+        pub max_duration_secs: Option<u64>,
+        /// Drop segments whose speech confidence (0-1) is below this. Requires a model
+        /// that supports `verbose_json` responses, such as `whisper-1`.
+        pub min_speech_confidence: Option<f32>,
+        /// Cut the audio at pauses and transcribe each piece separately.
+        pub split_on_silence: Option<SilenceSplit>,
+        /// Transcribe the left and right channels of a stereo file separately.
+        pub per_channel: bool,
+        /// Sampling temperature (0-1). `None` leaves the API default.
+        pub temperature: Option<f32>,
+        /// Subtitle formats only apply to a single request; splitting, per-channel
+        /// and confidence filtering always produce text.
+        pub format: TranscriptFormat,
+        /// Return a `start<TAB>word` line per word instead of the flat text. Needs a
+        /// model with word timestamps, such as `whisper-1`; others fall back to text.
+        pub word_timestamps: bool,
+    }
+
+    /// Parameters for ffmpeg's `silencedetect` filter.
+    #[derive(Debug, Clone, Copy)]
+    pub struct SilenceSplit {
+        /// Audio quieter than this (in dB) counts as silence, e.g. `-30`.
+        pub threshold_db: f64,
+        /// Only pauses at least this long split the audio.
+        pub min_duration_secs: f64,
+        /// Each segment after the first starts this much before its cut point, so a
+        /// word at the seam is heard whole by one of the two requests.
+        pub overlap_secs: f64,
+    }
+
+    /// Longest run of words at a seam that [`strip_seam_overlap`] removes.
+    const MAX_SEAM_OVERLAP_WORDS: usize = 12;
 
     /// Converts audio to mp3 using ffmpeg if needed.
     /// Returns the path to the (potentially converted) mp3 file.
     /// The output mp3 is placed in a temporary directory managed by the caller.
-    fn ensure_mp3(input: &Path, temp_dir_path: &Path) -> Result<PathBuf> {
+    /// When `max_duration_secs` is set the audio is always re-encoded, truncated to that length.
+    pub(crate) fn ensure_mp3(
+        input: &Path,
+        temp_dir_path: &Path,
+        max_duration_secs: Option<u64>,
+    ) -> Result<PathBuf> {
         let input_extension = input.extension().unwrap_or_default().to_ascii_lowercase();
 
-        if input_extension == "mp3" {
+        if input_extension == "mp3" && max_duration_secs.is_none() {
             // If it's already mp3, we can try using it directly.
             // Copying might be safer if the original path is weird, but let's try direct first.
             return Ok(input.to_path_buf());
@@ -34,10 +100,17 @@ pub mod trans {
             "Converting audio {:?} to temporary MP3: {:?}",
             input, output_mp3_path
         );
+        // The 64 kbps mono MP3 is rarely larger than its input.
+        crate::tempfiles::ensure_free_space(file_len(input))?;
 
         // `ffmpeg -i input.ext -vn -ar 16000 -ac 1 -b:a 64k -f mp3 output.mp3`
         // Use lower bitrate and sample rate for smaller files while keeping speech intelligible
-        let ffmpeg_output = Command::new("ffmpeg")
+        let mut command = Command::new("ffmpeg");
+        if let Some(secs) = max_duration_secs {
+            println!("Truncating audio to the first {} seconds.", secs);
+            command.args(["-t", &secs.to_string()]);
+        }
+        let ffmpeg_output = command
             .args([
                 "-i",
                 input
@@ -68,6 +141,7 @@ pub mod trans {
                         stderr
                     );
                 }
+                ensure_nonempty_audio(&output_mp3_path, input)?;
                 println!("ffmpeg conversion successful.");
                 Ok(output_mp3_path)
             }
@@ -84,25 +158,426 @@ pub mod trans {
         }
     }
 
+    /// Size of `path` in bytes, 0 if it can't be read.
+    pub fn file_len(path: &Path) -> u64 {
+        std::fs::metadata(path).map_or(0, |metadata| metadata.len())
+    }
+
+    /// Fails clearly when ffmpeg exited successfully but wrote no audio, which happens
+    /// when `source` has no audio stream. Otherwise the upload fails much later with a
+    /// confusing "Audio file is empty".
+    pub fn ensure_nonempty_audio(output: &Path, source: &Path) -> Result<()> {
+        let len = std::fs::metadata(output)
+            .with_context(|| format!("ffmpeg reported success but wrote no file at {:?}", output))?
+            .len();
+        if len == 0 {
+            bail!(
+                "ffmpeg produced an empty audio file from {:?}. It most likely has no audio stream.",
+                source
+            );
+        }
+        Ok(())
+    }
+
+    /// Returns the duration of an audio/video file in seconds, using ffprobe.
+    pub fn probe_duration_secs(input: &Path) -> Result<f64> {
+        let output = Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-show_entries",
+                "format=duration",
+                "-of",
+                "default=noprint_wrappers=1:nokey=1",
+            ])
+            .arg(input)
+            .output()
+            .context(
+                "Failed to execute ffprobe. Is ffmpeg (with ffprobe) installed and in PATH?",
+            )?;
+        if !output.status.success() {
+            bail!(
+                "ffprobe failed (Status: {}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .trim()
+            .parse::<f64>()
+            .with_context(|| format!("ffprobe returned an unreadable duration: {:?}", stdout))
+    }
+
+    /// Returns the channel count of the first audio stream (ffprobe).
+    fn probe_channel_count(input: &Path) -> Result<u32> {
+        let output = Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-select_streams",
+                "a:0",
+                "-show_entries",
+                "stream=channels",
+                "-of",
+                "default=noprint_wrappers=1:nokey=1",
+            ])
+            .arg(input)
+            .output()
+            .with_context(|| {
+                "Failed to execute ffprobe. Is ffmpeg (with ffprobe) installed and in PATH?"
+            })?;
+        if !output.status.success() {
+            bail!(
+                "ffprobe failed (Status: {}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .trim()
+            .parse::<u32>()
+            .with_context(|| format!("ffprobe returned an unreadable channel count: {:?}", stdout))
+    }
+
+    /// Splits a stereo file into left and right mono mp3 files in `temp_dir_path`,
+    /// encoded like [`ensure_mp3`] output.
+    fn split_stereo_channels(
+        input: &Path,
+        temp_dir_path: &Path,
+        max_duration_secs: Option<u64>,
+    ) -> Result<(PathBuf, PathBuf)> {
+        let left = temp_dir_path.join("channel_left.mp3");
+        let right = temp_dir_path.join("channel_right.mp3");
+        println!(
+            "Splitting stereo audio {:?} into left/right channels...",
+            input
+        );
+
+        let mut command = Command::new("ffmpeg");
+        if let Some(secs) = max_duration_secs {
+            command.args(["-t", &secs.to_string()]);
+        }
+        command.arg("-i").arg(input).args([
+            "-filter_complex",
+            "[0:a]channelsplit=channel_layout=stereo[l][r]",
+        ]);
+        for (label, path) in [("[l]", &left), ("[r]", &right)] {
+            command
+                .args([
+                    "-map", label, "-ar", "16000", "-b:a", "64k", "-f", "mp3", "-y",
+                ])
+                .arg(path);
+        }
+        let output = command
+            .output()
+            .with_context(|| "Failed to execute ffmpeg to split channels")?;
+        if !output.status.success() {
+            bail!(
+                "ffmpeg failed to split channels (Status: {}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok((left, right))
+    }
+
+    /// Finds pauses with ffmpeg's `silencedetect` filter and returns the
+    /// `(silence_start, silence_end)` pairs in seconds.
+    fn detect_silences(input: &Path, split: SilenceSplit) -> Result<Vec<(f64, f64)>> {
+        let filter = format!(
+            "silencedetect=noise={}dB:d={}",
+            split.threshold_db, split.min_duration_secs
+        );
+        let output = Command::new("ffmpeg")
+            .arg("-i")
+            .arg(input)
+            .arg("-af")
+            .arg(&filter)
+            .arg("-f")
+            .arg("null")
+            .arg("-")
+            .output()
+            .with_context(|| {
+                "Failed to execute ffmpeg for silence detection. Is ffmpeg installed and in PATH?"
+            })?;
+        if !output.status.success() {
+            bail!(
+                "ffmpeg silence detection failed (Status: {}): {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        // ffmpeg logs e.g. `silence_start: 12.3` then `silence_end: 14.1 | silence_duration: 1.8`.
+        let value_after = |line: &str, key: &str| -> Option<f64> {
+            let rest = &line[line.find(key)? + key.len()..];
+            rest.split_whitespace().next()?.parse().ok()
+        };
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut silences = Vec::new();
+        let mut start = None;
+        for line in stderr.lines() {
+            if let Some(value) = value_after(line, "silence_start:") {
+                start = Some(value);
+            } else if let Some(end) = value_after(line, "silence_end:") {
+                if let Some(start) = start.take() {
+                    silences.push((start, end));
+                }
+            }
+        }
+        Ok(silences)
+    }
+
+    /// Cuts `input` at the middle of each detected pause, writing the pieces as mp3
+    /// files into `temp_dir_path`. Returns the input itself when there are no pauses.
+    fn split_at_silences(
+        input: &Path,
+        temp_dir_path: &Path,
+        split: SilenceSplit,
+    ) -> Result<Vec<PathBuf>> {
+        let cut_points: Vec<f64> = detect_silences(input, split)?
+            .into_iter()
+            .map(|(start, end)| (start + end) / 2.0)
+            .filter(|&point| point > 0.0)
+            .collect();
+        if cut_points.is_empty() {
+            println!("No pauses found; transcribing the audio as one piece.");
+            return Ok(vec![input.to_path_buf()]);
+        }
+        println!(
+            "Splitting audio at {} pauses into {} segments.",
+            cut_points.len(),
+            cut_points.len() + 1
+        );
+
+        // The segments together are about as large as the whole file.
+        crate::tempfiles::ensure_free_space(file_len(input))?;
+        let mut bounds = vec![0.0];
+        bounds.extend(cut_points);
+        let mut segments = Vec::with_capacity(bounds.len());
+        for (index, &cut) in bounds.iter().enumerate() {
+            let segment_path = temp_dir_path.join(format!("segment_{:03}.mp3", index));
+            let start = (cut - split.overlap_secs).max(0.0);
+            let mut command = Command::new("ffmpeg");
+            command
+                .arg("-i")
+                .arg(input)
+                .arg("-ss")
+                .arg(start.to_string());
+            if let Some(&end) = bounds.get(index + 1) {
+                command.arg("-to").arg(end.to_string());
+            }
+            let output = command
+                .arg("-vn")
+                .arg("-y")
+                .arg(&segment_path)
+                .output()
+                .with_context(|| "Failed to execute ffmpeg to cut an audio segment")?;
+            if !output.status.success() {
+                bail!(
+                    "ffmpeg failed to cut segment {} (Status: {}): {}",
+                    index + 1,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr)
+                );
+            }
+            segments.push(segment_path);
+        }
+        Ok(segments)
+    }
+
+    /// Rough 0-1 likelihood that a segment is real speech rather than a hallucination
+    /// over silence or music.
+    fn speech_confidence(segment: &TranscriptionSegment) -> f32 {
+        (1.0 - segment.no_speech_prob) * segment.avg_logprob.exp()
+    }
+
+    /// Requests word-level timestamps and formats them as a `start<TAB>word` table.
+    async fn transcribe_words(
+        client: &Client<OpenAIConfig>,
+        input_mp3_path: &Path,
+        options: &TranscribeOptions,
+    ) -> Result<String> {
+        let mut request_args = CreateTranscriptionRequestArgs::default();
+        request_args
+            .file(input_mp3_path)
+            .model(&options.model)
+            .response_format(AudioResponseFormat::VerboseJson)
+            .timestamp_granularities(vec![TimestampGranularity::Word]);
+        if let Some(temperature) = options.temperature {
+            request_args.temperature(temperature);
+        }
+        let request = request_args
+            .build()
+            .context("Failed to build OpenAI transcription request")?;
+
+        println!("Sending word timestamp transcription request to OpenAI...");
+        let response = client
+            .audio()
+            .transcribe_verbose_json(request)
+            .await
+            .context("OpenAI API request for transcription failed")?;
+        let words = response
+            .words
+            .filter(|words| !words.is_empty())
+            .ok_or_else(|| anyhow!("the response had no words"))?;
+        println!("Received timestamps for {} words.", words.len());
+        Ok(word_table(&words))
+    }
+
+    fn word_table(words: &[TranscriptionWord]) -> String {
+        words
+            .iter()
+            .map(|word| format!("{:.2}\t{}", word.start, word.word.trim()))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Joins the segments at or above `min_confidence`, failing if none remain.
+    fn filter_low_confidence(
+        segments: &[TranscriptionSegment],
+        min_confidence: f32,
+    ) -> Result<String> {
+        let mut kept = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let confidence = speech_confidence(segment);
+            if confidence >= min_confidence {
+                kept.push(segment.text.trim());
+            } else {
+                println!(
+                    "Dropping low-confidence segment ({:.2} < {:.2}, {:.1}s-{:.1}s): {:?}",
+                    confidence, min_confidence, segment.start, segment.end, segment.text
+                );
+            }
+        }
+        if kept.is_empty() && !segments.is_empty() {
+            bail!(
+                "All {} transcription segments were below the speech confidence threshold ({:.2}). The audio is probably silent or music; refusing to paste.",
+                segments.len(),
+                min_confidence
+            );
+        }
+        Ok(kept.join(" "))
+    }
+
     pub async fn transcribe(
         client: &Client<OpenAIConfig>,
         input_audio_path: &Path,
+        options: &TranscribeOptions,
     ) -> Result<String> {
         // Changed return type to anyhow::Result
 
         // Create a temporary directory for potential ffmpeg conversion
-        let temp_dir =
-            tempdir().context("Failed to create temporary directory for audio processing")?;
+        let temp_dir = crate::tempfiles::dir("transcribe")?;
+
+        if options.per_channel {
+            let channels = probe_channel_count(input_audio_path)?;
+            if channels != 2 {
+                bail!(
+                    "--per-channel needs a stereo file, but {:?} has {} channel(s).",
+                    input_audio_path,
+                    channels
+                );
+            }
+            let (left, right) = split_stereo_channels(
+                input_audio_path,
+                temp_dir.path(),
+                options.max_duration_secs,
+            )?;
+            println!("Transcribing left channel...");
+            let left_text = transcribe_prepared(client, &left, temp_dir.path(), options).await?;
+            println!("Transcribing right channel...");
+            let right_text = transcribe_prepared(client, &right, temp_dir.path(), options).await?;
+            return Ok(format!(
+                "[L] {}\n\n[R] {}",
+                left_text.trim(),
+                right_text.trim()
+            ));
+        }
 
         // Ensure we have an MP3 file, converting if necessary
-        let input_mp3_path = ensure_mp3(input_audio_path, temp_dir.path())
-            .context("Failed to prepare MP3 file for transcription")?;
+        let input_mp3_path =
+            ensure_mp3(input_audio_path, temp_dir.path(), options.max_duration_secs)
+                .context("Failed to prepare MP3 file for transcription")?;
 
         println!("Using audio file for transcription: {:?}", input_mp3_path);
+        transcribe_prepared(client, &input_mp3_path, temp_dir.path(), options).await
+
+        // The temp_dir (and the converted mp3 within it, if created)
+        // will be automatically deleted when `temp_dir` goes out of scope here.
+    }
+
+    /// Transcribes a prepared mp3, piece by piece when `--split-on-silence` is set.
+    async fn transcribe_prepared(
+        client: &Client<OpenAIConfig>,
+        input_mp3_path: &Path,
+        temp_dir_path: &Path,
+        options: &TranscribeOptions,
+    ) -> Result<String> {
+        let Some(split) = options.split_on_silence else {
+            return transcribe_mp3(client, input_mp3_path, options).await;
+        };
+        let segments = split_at_silences(input_mp3_path, temp_dir_path, split)?;
+        let mut texts: Vec<String> = Vec::with_capacity(segments.len());
+        for (index, segment_path) in segments.iter().enumerate() {
+            println!("Transcribing segment {}/{}...", index + 1, segments.len());
+            let text = transcribe_mp3(client, segment_path, options).await?;
+            let text = match texts.last() {
+                Some(previous) if split.overlap_secs > 0.0 => strip_seam_overlap(previous, &text),
+                _ => text.trim(),
+            };
+            if !text.is_empty() {
+                texts.push(text.to_string());
+            }
+        }
+        Ok(texts.join("\n\n"))
+    }
+
+    /// Drops the words at the start of `next` that repeat the end of `previous`, as
+    /// overlapping segments transcribe the seam twice. Words compare case-insensitively
+    /// and ignoring punctuation; the longest repeated run (up to
+    /// `MAX_SEAM_OVERLAP_WORDS`) wins.
+    fn strip_seam_overlap<'a>(previous: &str, next: &'a str) -> &'a str {
+        let normalize = |word: &str| -> String {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        };
+        let previous_words: Vec<String> = previous.split_whitespace().map(normalize).collect();
+        let next_words: Vec<String> = next.split_whitespace().map(normalize).collect();
+
+        let max_len = MAX_SEAM_OVERLAP_WORDS
+            .min(previous_words.len())
+            .min(next_words.len());
+        let repeated = (1..=max_len).rev().find(|&len| {
+            let tail = &previous_words[previous_words.len() - len..];
+            tail.iter()
+                .zip(&next_words[..len])
+                .all(|(a, b)| !a.is_empty() && a == b)
+        });
+        let mut rest = next.trim();
+        if let Some(len) = repeated {
+            println!("Removed {} repeated word(s) at a segment seam.", len);
+            for _ in 0..len {
+                let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+                rest = rest[word_end..].trim_start();
+            }
+        }
+        rest
+    }
 
+    /// Uploads one mp3 file to the transcription API.
+    async fn transcribe_mp3(
+        client: &Client<OpenAIConfig>,
+        input_mp3_path: &Path,
+        options: &TranscribeOptions,
+    ) -> Result<String> {
         // Check file size before uploading (OpenAI has a 25MB limit)
         let metadata =
-            std::fs::metadata(&input_mp3_path).context("Failed to get metadata for audio file")?;
+            std::fs::metadata(input_mp3_path).context("Failed to get metadata for audio file")?;
         if metadata.len() > 25 * 1024 * 1024 {
             // Approx 25MB
             return Err(anyhow!(
@@ -114,17 +589,76 @@ pub mod trans {
             return Err(anyhow!("Audio file is empty."));
         }
 
+        if options.word_timestamps {
+            match transcribe_words(client, input_mp3_path, options).await {
+                Ok(table) => return Ok(table),
+                Err(e) => println!(
+                    "Warning: Word timestamps unavailable ({:#}). Transcribing as plain text.",
+                    e
+                ),
+            }
+        }
+
         // Build the transcription request
         // Consider making the prompt configurable if needed later
-        let request = CreateTranscriptionRequestArgs::default()
-            .file(input_mp3_path) // Pass the PathBuf directly
-            .model("whisper-1")
-            // .prompt("Optional prompt to guide the model.")
+        let mut request_args = CreateTranscriptionRequestArgs::default();
+        request_args
+            .file(input_mp3_path) // Pass the path directly
+            .model(&options.model);
+        // .prompt("Optional prompt to guide the model.")
+        if let Some(temperature) = options.temperature {
+            request_args.temperature(temperature);
+        }
+        if options.min_speech_confidence.is_some() {
+            request_args
+                .response_format(AudioResponseFormat::VerboseJson)
+                .timestamp_granularities(vec![TimestampGranularity::Segment]);
+        } else {
+            match options.format {
+                TranscriptFormat::Text => {}
+                TranscriptFormat::Srt => {
+                    request_args.response_format(AudioResponseFormat::Srt);
+                }
+                TranscriptFormat::Vtt => {
+                    request_args.response_format(AudioResponseFormat::Vtt);
+                }
+            }
+        }
+        let request = request_args
             .build()
             .context("Failed to build OpenAI transcription request")?;
 
         println!("Sending transcription request to OpenAI...");
 
+        if let Some(min_confidence) = options.min_speech_confidence {
+            let response = client
+                .audio()
+                .transcribe_verbose_json(request)
+                .await
+                .context("OpenAI API request for transcription failed")?;
+            println!("Transcription received from OpenAI.");
+            return match response.segments {
+                Some(segments) => filter_low_confidence(&segments, min_confidence),
+                None => {
+                    println!(
+                        "Warning: Response had no segments; skipping the speech confidence check."
+                    );
+                    Ok(response.text)
+                }
+            };
+        }
+
+        if options.format != TranscriptFormat::Text {
+            let subtitles = client
+                .audio()
+                .transcribe_raw(request)
+                .await
+                .context("OpenAI API request for transcription failed")?;
+            println!("{:?} transcription received from OpenAI.", options.format);
+            return String::from_utf8(subtitles.to_vec())
+                .context("Transcription response was not valid UTF-8");
+        }
+
         // Perform the transcription
         let response = client
             .audio()
@@ -134,8 +668,5 @@ pub mod trans {
 
         println!("Transcription received from OpenAI.");
         Ok(response.text)
-
-        // The temp_dir (and the converted mp3 within it, if created)
-        // will be automatically deleted when `temp_dir` goes out of scope here.
     }
 }
@@ -0,0 +1,164 @@
+// src/uia.rs
+// The slice of UI Automation that OCRP calls. winapi 0.3.9 ships no UI Automation
+// module, so the interfaces are declared here with its RIDL! macro, in vtable order
+// up to the last method used.
+#![allow(non_snake_case, non_upper_case_globals, clippy::upper_case_acronyms)]
+
+use std::{ops::Deref, ptr::NonNull};
+use winapi::{
+    shared::{
+        minwindef::{BOOL, LPVOID},
+        windef::{HWND, POINT},
+        winerror::{HRESULT, SUCCEEDED},
+        wtypesbase::CLSCTX_INPROC_SERVER,
+    },
+    um::{
+        combaseapi::{CoCreateInstance, CoInitializeEx, CoUninitialize},
+        oaidl::{SAFEARRAY, VARIANT},
+        objbase::COINIT_MULTITHREADED,
+        unknwnbase::{IUnknown, IUnknownVtbl},
+    },
+    Class, Interface, RIDL,
+};
+
+pub type PROPERTYID = i32;
+pub const UIA_IsPasswordPropertyId: PROPERTYID = 30019;
+
+RIDL! {#[uuid(0xff48dba4, 0x60ef, 0x4201, 0xaa, 0x87, 0x54, 0x10, 0x3e, 0xef, 0x59, 0x4e)]
+class CUIAutomation;}
+
+RIDL! {#[uuid(0x30cbe57d, 0xd9d0, 0x452a, 0xab, 0x13, 0x7a, 0xc5, 0xac, 0x48, 0x25, 0xee)]
+interface IUIAutomation(IUIAutomationVtbl): IUnknown(IUnknownVtbl) {
+    fn CompareElements(
+        el1: *mut IUIAutomationElement,
+        el2: *mut IUIAutomationElement,
+        areSame: *mut BOOL,
+    ) -> HRESULT,
+    fn CompareRuntimeIds(
+        runtimeId1: *mut SAFEARRAY,
+        runtimeId2: *mut SAFEARRAY,
+        areSame: *mut BOOL,
+    ) -> HRESULT,
+    fn GetRootElement(
+        root: *mut *mut IUIAutomationElement,
+    ) -> HRESULT,
+    fn ElementFromHandle(
+        hwnd: HWND,
+        element: *mut *mut IUIAutomationElement,
+    ) -> HRESULT,
+    fn ElementFromPoint(
+        pt: POINT,
+        element: *mut *mut IUIAutomationElement,
+    ) -> HRESULT,
+    fn GetFocusedElement(
+        element: *mut *mut IUIAutomationElement,
+    ) -> HRESULT,
+}}
+
+// Conditions and cache requests are never built here, so they stay untyped.
+RIDL! {#[uuid(0xd22108aa, 0x8ac5, 0x49a5, 0x83, 0x7b, 0x37, 0xbb, 0xb3, 0xd7, 0x59, 0x1e)]
+interface IUIAutomationElement(IUIAutomationElementVtbl): IUnknown(IUnknownVtbl) {
+    fn SetFocus() -> HRESULT,
+    fn GetRuntimeId(
+        runtimeId: *mut *mut SAFEARRAY,
+    ) -> HRESULT,
+    fn FindFirst(
+        scope: i32,
+        condition: LPVOID,
+        found: *mut *mut IUIAutomationElement,
+    ) -> HRESULT,
+    fn FindAll(
+        scope: i32,
+        condition: LPVOID,
+        found: *mut LPVOID,
+    ) -> HRESULT,
+    fn FindFirstBuildCache(
+        scope: i32,
+        condition: LPVOID,
+        cacheRequest: LPVOID,
+        found: *mut *mut IUIAutomationElement,
+    ) -> HRESULT,
+    fn FindAllBuildCache(
+        scope: i32,
+        condition: LPVOID,
+        cacheRequest: LPVOID,
+        found: *mut LPVOID,
+    ) -> HRESULT,
+    fn BuildUpdatedCache(
+        cacheRequest: LPVOID,
+        updatedElement: *mut *mut IUIAutomationElement,
+    ) -> HRESULT,
+    fn GetCurrentPropertyValue(
+        propertyId: PROPERTYID,
+        retVal: *mut VARIANT,
+    ) -> HRESULT,
+}}
+
+/// An owned COM interface pointer, released on drop.
+pub struct ComPtr<T: Deref<Target = IUnknown>>(NonNull<T>);
+
+impl<T: Deref<Target = IUnknown>> ComPtr<T> {
+    /// Takes ownership of the reference returned through an out-parameter.
+    /// `None` for a null pointer.
+    ///
+    /// # Safety
+    /// `ptr` must be null or a live interface pointer whose reference is ours to release.
+    pub unsafe fn from_raw(ptr: *mut T) -> Option<Self> {
+        NonNull::new(ptr).map(ComPtr)
+    }
+}
+
+impl<T: Deref<Target = IUnknown>> Deref for ComPtr<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        unsafe { self.0.as_ref() }
+    }
+}
+
+impl<T: Deref<Target = IUnknown>> Drop for ComPtr<T> {
+    fn drop(&mut self) {
+        unsafe {
+            self.0.as_ref().Release();
+        }
+    }
+}
+
+/// COM on the current thread, initialized once and uninitialized when the thread exits.
+struct ComApartment {
+    /// Whether our `CoInitializeEx` succeeded and needs a matching `CoUninitialize`.
+    /// It fails when the thread already runs another apartment model, which works too.
+    owned: bool,
+}
+
+impl Drop for ComApartment {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
+
+thread_local! {
+    static APARTMENT: ComApartment = ComApartment {
+        owned: SUCCEEDED(unsafe { CoInitializeEx(std::ptr::null_mut(), COINIT_MULTITHREADED) }),
+    };
+}
+
+/// Creates the UI Automation client, initializing COM for this thread on first use.
+pub fn automation() -> Option<ComPtr<IUIAutomation>> {
+    APARTMENT.with(|_| ());
+    unsafe {
+        let mut automation: *mut IUIAutomation = std::ptr::null_mut();
+        let hr = CoCreateInstance(
+            &CUIAutomation::uuidof(),
+            std::ptr::null_mut(),
+            CLSCTX_INPROC_SERVER,
+            &IUIAutomation::uuidof(),
+            &mut automation as *mut _ as *mut LPVOID,
+        );
+        if !SUCCEEDED(hr) {
+            return None;
+        }
+        ComPtr::from_raw(automation)
+    }
+}
@@ -0,0 +1,62 @@
+// src/webhook.rs
+// `--webhook-url`: posts each recognized text as JSON, in the background.
+
+use serde::Serialize;
+use std::{
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Serialize)]
+struct Payload {
+    text: String,
+    /// What was processed, as in `--log-file` records, e.g. `image`.
+    kind: String,
+    /// What started the run, e.g. `hotkey`.
+    source: String,
+    /// Seconds since the Unix epoch.
+    timestamp: u64,
+}
+
+/// Posts `text` to `url` on a separate thread so the paste never waits on the network.
+/// The response status is logged; failures only warn.
+pub fn post(url: &str, timeout: Duration, text: &str, kind: &str, source: &str) {
+    let url = url.to_string();
+    let payload = Payload {
+        text: text.to_string(),
+        kind: kind.to_string(),
+        source: source.to_string(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    thread::spawn(move || {
+        let rt = match tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+        {
+            Ok(rt) => rt,
+            Err(e) => {
+                eprintln!("Warning: Webhook not sent, no Tokio runtime: {}", e);
+                return;
+            }
+        };
+        let result = rt.block_on(async {
+            reqwest::Client::builder()
+                .timeout(timeout)
+                .build()?
+                .post(&url)
+                .json(&payload)
+                .send()
+                .await
+        });
+        match result {
+            Ok(response) if response.status().is_success() => {
+                println!("Webhook accepted the result ({}).", response.status())
+            }
+            Ok(response) => eprintln!("Warning: Webhook returned {}.", response.status()),
+            Err(e) => eprintln!("Warning: Webhook request failed: {}", e),
+        }
+    });
+}
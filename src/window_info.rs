@@ -0,0 +1,180 @@
+// src/window_info.rs
+// Win32 helpers for identifying which process owns a window and what has focus,
+// plus the process DPI awareness.
+
+use crate::uia;
+use std::{ffi::OsString, os::windows::ffi::OsStringExt, path::Path};
+use winapi::{
+    shared::{
+        minwindef::DWORD,
+        windef::{
+            DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, DPI_AWARENESS_CONTEXT_SYSTEM_AWARE,
+            DPI_AWARENESS_CONTEXT_UNAWARE, HWND,
+        },
+        winerror::SUCCEEDED,
+        wtypes::{VARIANT_TRUE, VT_BOOL},
+    },
+    um::{
+        handleapi::CloseHandle,
+        oaidl::VARIANT,
+        oleauto::VariantClear,
+        processthreadsapi::OpenProcess,
+        winbase::QueryFullProcessImageNameW,
+        winnt::PROCESS_QUERY_LIMITED_INFORMATION,
+        winuser::{
+            GetForegroundWindow, GetGUIThreadInfo, GetOpenClipboardWindow, GetWindowTextW,
+            GetWindowThreadProcessId, SetForegroundWindow, SetProcessDpiAwarenessContext,
+            GUITHREADINFO, GUI_CARETBLINKING,
+        },
+    },
+};
+
+/// Returns the full executable path of the process that owns `hwnd`.
+pub fn process_path_for_window(hwnd: HWND) -> Option<String> {
+    if hwnd.is_null() {
+        return None;
+    }
+    unsafe {
+        let mut pid: DWORD = 0;
+        GetWindowThreadProcessId(hwnd, &mut pid);
+        if pid == 0 {
+            return None;
+        }
+
+        let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if process.is_null() {
+            return None;
+        }
+        let mut buffer = [0u16; 1024];
+        let mut len = buffer.len() as DWORD;
+        let ok = QueryFullProcessImageNameW(process, 0, buffer.as_mut_ptr(), &mut len);
+        CloseHandle(process);
+        if ok == 0 {
+            return None;
+        }
+        Some(
+            OsString::from_wide(&buffer[..len as usize])
+                .to_string_lossy()
+                .into_owned(),
+        )
+    }
+}
+
+/// Returns just the executable file name (e.g. `ditto.exe`) of the process that owns `hwnd`.
+pub fn process_name_for_window(hwnd: HWND) -> Option<String> {
+    let path = process_path_for_window(hwnd)?;
+    Path::new(&path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// Best guess at the executable currently holding the clipboard open.
+pub fn clipboard_holder_name() -> Option<String> {
+    let hwnd = unsafe { GetOpenClipboardWindow() };
+    if hwnd.is_null() {
+        return None;
+    }
+    process_name_for_window(hwnd)
+}
+
+/// The window that currently has keyboard focus, as seen by the user.
+#[derive(Debug)]
+pub struct ForegroundWindow {
+    pub exe_name: Option<String>,
+    pub title: String,
+}
+
+pub fn foreground_window() -> Option<ForegroundWindow> {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return None;
+        }
+        let mut buffer = [0u16; 512];
+        let len = GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+        let title = String::from_utf16_lossy(&buffer[..len.max(0) as usize]);
+        Some(ForegroundWindow {
+            exe_name: process_name_for_window(hwnd),
+            title,
+        })
+    }
+}
+
+/// Whether the focused control of the foreground window shows a text caret, i.e. most
+/// likely accepts typed or pasted text. Apps that draw their own caret (some browsers and
+/// Electron apps) may not report one.
+pub fn focused_control_has_caret() -> bool {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_null() {
+            return false;
+        }
+        let thread_id = GetWindowThreadProcessId(hwnd, std::ptr::null_mut());
+        let mut info: GUITHREADINFO = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<GUITHREADINFO>() as DWORD;
+        if GetGUIThreadInfo(thread_id, &mut info) == 0 {
+            return false;
+        }
+        !info.hwndCaret.is_null() || info.flags & GUI_CARETBLINKING != 0
+    }
+}
+
+/// Whether UI Automation reports the focused element as a password field. `None`
+/// when that can't be determined (UI Automation unavailable or nothing focused).
+pub fn focused_is_password() -> Option<bool> {
+    let automation = uia::automation()?;
+    unsafe {
+        let mut element = std::ptr::null_mut();
+        if !SUCCEEDED(automation.GetFocusedElement(&mut element)) {
+            return None;
+        }
+        let element = uia::ComPtr::from_raw(element)?;
+        let mut value: VARIANT = std::mem::zeroed();
+        if !SUCCEEDED(element.GetCurrentPropertyValue(uia::UIA_IsPasswordPropertyId, &mut value)) {
+            return None;
+        }
+        let is_password = (value.n1.n2().vt == VT_BOOL as u16)
+            .then(|| *value.n1.n2().n3.boolVal() == VARIANT_TRUE);
+        VariantClear(&mut value);
+        is_password
+    }
+}
+
+/// The raw foreground window handle, for handing focus back later with [`reassert_foreground`].
+pub fn foreground_handle() -> HWND {
+    unsafe { GetForegroundWindow() }
+}
+
+/// Brings `hwnd` back to the foreground if something else took focus.
+/// Returns `false` if Windows refused the switch.
+pub fn reassert_foreground(hwnd: HWND) -> bool {
+    unsafe {
+        if hwnd.is_null() || GetForegroundWindow() == hwnd {
+            return true;
+        }
+        SetForegroundWindow(hwnd) != 0
+    }
+}
+
+/// How the process reports DPI awareness to Windows (`--dpi-awareness`).
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DpiAwareness {
+    /// Physical pixels on every monitor, even with mixed scaling.
+    PerMonitor,
+    /// Physical pixels at the primary monitor's scale; other monitors are bitmap-scaled.
+    System,
+    /// Windows virtualizes all coordinates to 96 DPI.
+    Unaware,
+}
+
+/// Sets the process DPI awareness, so screen coordinates and window sizes come back
+/// in physical pixels. Must run before any window is created. Returns `false` if Windows
+/// refused, e.g. because a manifest already set it or on Windows 10 before 1703.
+pub fn set_dpi_awareness(awareness: DpiAwareness) -> bool {
+    let context = match awareness {
+        DpiAwareness::PerMonitor => DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2,
+        DpiAwareness::System => DPI_AWARENESS_CONTEXT_SYSTEM_AWARE,
+        DpiAwareness::Unaware => DPI_AWARENESS_CONTEXT_UNAWARE,
+    };
+    unsafe { SetProcessDpiAwarenessContext(context) != 0 }
+}
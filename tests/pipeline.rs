@@ -0,0 +1,63 @@
+// tests/pipeline.rs
+// The library API as an embedder sees it. These cases fail before any external
+// tool would run, so they need neither Tesseract nor an API key.
+
+use ocrp::input::{self, DirPolicy, InputSettings, UnknownFilePolicy};
+use ocrp::{process_content, Config, Content};
+use std::io::Cursor;
+
+fn uniform_png() -> Vec<u8> {
+    let image = image::RgbImage::from_pixel(64, 32, image::Rgb([255, 255, 255]));
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    png
+}
+
+#[test]
+fn blank_image_is_rejected_before_tesseract() {
+    let config = Config {
+        ocr: ocrp::OcrSettings {
+            tesseract_cmd: "ocrp-test-no-such-tesseract".to_string(),
+            ..ocrp::OcrSettings::default()
+        },
+        ..Config::default()
+    };
+    let error = process_content(Content::Image(uniform_png()), &config).unwrap_err();
+    assert!(error.to_string().contains("appears blank"), "{:#}", error);
+}
+
+#[test]
+fn audio_without_api_key_fails() {
+    let content = Content::AudioFile("ocrp-test-missing.wav".into());
+    let error = process_content(content, &Config::default()).unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("API Key is missing"),
+        "{:#}",
+        error
+    );
+}
+
+#[test]
+fn unknown_file_can_be_skipped() {
+    let settings = InputSettings {
+        unknown_file_policy: UnknownFilePolicy::Skip,
+        ..InputSettings::default()
+    };
+    let files = ["notes.ocrp-test".to_string()];
+    let text = input::process_files(&files, &settings, &Config::default()).unwrap();
+    assert_eq!(text, "");
+}
+
+#[test]
+fn copied_folder_follows_dir_policy() {
+    let dir = std::env::temp_dir();
+    let files = [dir.to_string_lossy().into_owned()];
+    let settings = InputSettings {
+        dir_policy: DirPolicy::Error,
+        ..InputSettings::default()
+    };
+    let error = input::process_files(&files, &settings, &Config::default()).unwrap_err();
+    assert!(error.to_string().contains("folder"), "{:#}", error);
+}